@@ -0,0 +1,44 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::time::Duration;
+use temporal_sdk::ActContext;
+use temporal_sdk_core::protos::coresdk::AsJsonPayloadExt;
+
+/// Heartbeat helpers layered on top of [ActContext].
+pub trait ActContextExt {
+    /// Records a heartbeat with `details` serialized as json.
+    fn heartbeat_json<T: Serialize>(&self, details: &T) -> Result<()>;
+
+    /// Records a heartbeat every `interval` until cancellation of the activity is requested,
+    /// then resolves. Race it against the activity's work to make the work cancel-safe:
+    ///
+    /// ```ignore
+    /// tokio::select! {
+    ///     res = do_work() => res,
+    ///     _ = ctx.heartbeat_loop(Duration::from_secs(1)) => bail!("activity cancelled"),
+    /// }
+    /// ```
+    ///
+    /// The activity must be scheduled with a heartbeat timeout for cancellation to be
+    /// delivered to it.
+    fn heartbeat_loop(&self, interval: Duration) -> BoxFuture<'_, ()>;
+}
+
+impl ActContextExt for ActContext {
+    fn heartbeat_json<T: Serialize>(&self, details: &T) -> Result<()> {
+        self.record_heartbeat(vec![details.as_json_payload()?]);
+        Ok(())
+    }
+
+    fn heartbeat_loop(&self, interval: Duration) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            loop {
+                tokio::select! {
+                    _ = self.cancelled() => return,
+                    _ = tokio::time::sleep(interval) => self.record_heartbeat(vec![]),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use once_cell::sync::OnceCell;
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use temporal_sdk::ActContext;
+
+static ACTIVITY_MIDDLEWARE: OnceCell<Vec<Box<dyn ActivityMiddleware>>> = OnceCell::new();
+
+/// Hooks run around every activity registered through [crate::registry::activity!], for
+/// cross-cutting concerns (timing, logging, metrics, pulling auth context out of the
+/// [ActContext]) that shouldn't be repeated in each activity. All hooks default to doing
+/// nothing.
+pub trait ActivityMiddleware: Send + Sync {
+    /// Runs before the activity function is called.
+    fn before(&self, _activity_type: &str, _ctx: &ActContext) {}
+
+    /// Runs after the activity function returns `Ok`.
+    fn after(&self, _activity_type: &str, _elapsed: Duration) {}
+
+    /// Runs after the activity function returns `Err`.
+    fn on_error(&self, _activity_type: &str, _error: &anyhow::Error, _elapsed: Duration) {}
+}
+
+/// Sets the middleware wrapped around every activity, run in order for `before` and in
+/// reverse for `after`/`on_error`. Call once at worker startup, before the workers are
+/// built; later calls are ignored.
+pub fn init(middleware: Vec<Box<dyn ActivityMiddleware>>) {
+    let _ = ACTIVITY_MIDDLEWARE.set(middleware);
+}
+
+fn middleware() -> &'static [Box<dyn ActivityMiddleware>] {
+    ACTIVITY_MIDDLEWARE.get_or_init(Vec::new)
+}
+
+/// Wraps `activity_fn` so the middleware set with [init] runs around each call.
+pub fn with_middleware<A, R, F, Fut>(
+    activity_type: &'static str,
+    activity_fn: F,
+) -> impl Fn(ActContext, A) -> BoxFuture<'static, Result<R>> + Send + Sync + 'static
+where
+    A: Send + 'static,
+    R: Send + 'static,
+    F: Fn(ActContext, A) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R>> + Send + 'static,
+{
+    let activity_fn = Arc::new(activity_fn);
+
+    move |ctx: ActContext, input: A| {
+        let activity_fn = activity_fn.clone();
+
+        Box::pin(async move {
+            for m in middleware() {
+                m.before(activity_type, &ctx);
+            }
+
+            let started = Instant::now();
+            let result = activity_fn(ctx, input).await;
+            let elapsed = started.elapsed();
+
+            for m in middleware().iter().rev() {
+                match &result {
+                    Ok(_) => m.after(activity_type, elapsed),
+                    Err(e) => m.on_error(activity_type, e, elapsed),
+                }
+            }
+
+            result
+        })
+    }
+}
+
+/// Logs how long each activity took and whether it failed.
+pub struct LogTiming;
+
+impl ActivityMiddleware for LogTiming {
+    fn before(&self, activity_type: &str, _ctx: &ActContext) {
+        tracing::info!(activity_type, "activity started");
+    }
+
+    fn after(&self, activity_type: &str, elapsed: Duration) {
+        tracing::info!(activity_type, ?elapsed, "activity completed");
+    }
+
+    fn on_error(&self, activity_type: &str, error: &anyhow::Error, elapsed: Duration) {
+        tracing::warn!(activity_type, ?elapsed, error = %format!("{:#}", error), "activity failed");
+    }
+}
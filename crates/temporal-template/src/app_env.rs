@@ -0,0 +1,64 @@
+use crate::env_vars::get_env_or_default;
+use anyhow::{bail, Context, Result};
+use std::{fmt, path::Path, str::FromStr};
+
+/// Deployment profile, set with `APP_ENV=dev|staging|prod` (default `dev`). Picks which
+/// `.env` and worker config files are loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppEnv {
+    #[default]
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl AppEnv {
+    pub fn from_env() -> Result<Self> {
+        get_env_or_default("APP_ENV", Self::default())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dev => "dev",
+            Self::Staging => "staging",
+            Self::Prod => "prod",
+        }
+    }
+
+    /// Loads `.env.<profile>` then `.env` from the working directory, if they exist.
+    /// Variables already set in the environment win, then the profile's file, so the
+    /// shared `.env` only fills in what's left.
+    pub fn load_dotenv(&self) -> Result<()> {
+        for file in [format!(".env.{}", self), ".env".to_string()] {
+            if Path::new(&file).exists() {
+                dotenvy::from_filename(&file)
+                    .with_context(|| format!("Failed to load {}", file))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `worker.<profile>.toml`, used when `WORKER_CONFIG` isn't set.
+    pub fn worker_config_file(&self) -> String {
+        format!("worker.{}.toml", self)
+    }
+}
+
+impl fmt::Display for AppEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AppEnv {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "dev" | "development" => Ok(Self::Dev),
+            "staging" => Ok(Self::Staging),
+            "prod" | "production" => Ok(Self::Prod),
+            _ => bail!("Unknown APP_ENV {}, expected dev, staging or prod", s),
+        }
+    }
+}
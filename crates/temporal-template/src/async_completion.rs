@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use temporal_sdk::ActContext;
+use temporal_sdk_core::{
+    protos::coresdk::AsJsonPayloadExt, Client, RetryClient, WorkflowClientTrait,
+};
+use temporal_sdk_core_protos::{
+    temporal::api::{common::v1::Payloads, failure::v1::Failure},
+    TaskToken,
+};
+
+/// The running activity's task token, encoded so it can be sent out-of-band (e.g. in a
+/// Slack button) and handed back to [complete_async] or [fail_async].
+///
+/// Return `ActExitValue::WillCompleteAsync` from the activity after sending the token
+/// somewhere; the activity then stays open until completed by token or until it times out.
+pub fn task_token(ctx: &ActContext) -> String {
+    base64::encode_config(&ctx.get_info().task_token, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode_task_token(token: &str) -> Result<TaskToken> {
+    let bytes = base64::decode_config(token, base64::URL_SAFE_NO_PAD)
+        .context("Task token is not valid base64")?;
+    Ok(TaskToken(bytes))
+}
+
+/// Completes the activity behind `token` with `result` serialized as json.
+pub async fn complete_async<T: Serialize>(
+    client: &RetryClient<Client>,
+    token: &str,
+    result: &T,
+) -> Result<()> {
+    client
+        .complete_activity_task(
+            decode_task_token(token)?,
+            Some(Payloads {
+                payloads: vec![result.as_json_payload()?],
+            }),
+        )
+        .await
+        .context("Failed to complete activity")?;
+
+    Ok(())
+}
+
+/// Fails the activity behind `token` with `message`. The activity's retry policy applies.
+pub async fn fail_async(client: &RetryClient<Client>, token: &str, message: &str) -> Result<()> {
+    client
+        .fail_activity_task(
+            decode_task_token(token)?,
+            Some(Failure {
+                message: message.to_string(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .context("Failed to fail activity")?;
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use crate::{client, settings::WorkerSettings};
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+use temporal_sdk_core::{Client, RetryClient};
+use tokio::sync::watch;
+
+/// When to watch a task queue's backlog and what counts as too much or too little work,
+/// for scaling workers from the worker itself.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct BacklogSettings {
+    /// How often to check the backlog. The watcher doesn't run when unset.
+    pub check_interval_secs: Option<u64>,
+    /// Backlog (workflow plus activity tasks) above which to ask for more workers.
+    pub scale_up_threshold: i64,
+    /// Backlog below which to ask for fewer workers.
+    pub scale_down_threshold: i64,
+}
+
+impl Default for BacklogSettings {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: None,
+            scale_up_threshold: 100,
+            scale_down_threshold: 1,
+        }
+    }
+}
+
+/// Tasks waiting on a task queue. The server only reports approximate counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Backlog {
+    pub workflow_tasks: i64,
+    pub activity_tasks: i64,
+}
+
+impl Backlog {
+    pub fn total(&self) -> i64 {
+        self.workflow_tasks + self.activity_tasks
+    }
+}
+
+/// Passed to the [watch_backlog] callback when the backlog crosses a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleSignal {
+    ScaleUp,
+    ScaleDown,
+}
+
+/// Asks the server for `task_queue`'s current backlog.
+pub async fn describe_backlog(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    task_queue: &str,
+) -> Result<Backlog> {
+    let description = client::describe_task_queue(client, namespace, task_queue).await?;
+
+    Ok(Backlog {
+        workflow_tasks: description.workflow_backlog,
+        activity_tasks: description.activity_backlog,
+    })
+}
+
+/// Checks `task_queue`'s backlog every `backlog.check_interval_secs`, logging it each time
+/// and calling `on_threshold` when it rises above or falls below the configured thresholds.
+/// The callback is called once per crossing, not on every check. The [BacklogSettings]
+/// follow config reloads (see [crate::config_reload]). Runs forever; spawn it.
+pub async fn watch_backlog(
+    client: RetryClient<Client>,
+    namespace: String,
+    task_queue: String,
+    mut settings: watch::Receiver<WorkerSettings>,
+    on_threshold: impl Fn(ScaleSignal, &Backlog) + Send,
+) {
+    let mut last_signal = None;
+
+    loop {
+        let backlog_settings = settings.borrow().backlog.clone();
+        let interval = match backlog_settings.check_interval_secs {
+            Some(secs) => Duration::from_secs(secs),
+            // off until a reloaded config turns it on
+            None => match settings.changed().await {
+                Ok(()) => continue,
+                Err(_) => return,
+            },
+        };
+
+        match describe_backlog(&client, &namespace, &task_queue).await {
+            Ok(backlog) => {
+                tracing::info!(
+                    %task_queue,
+                    workflow_backlog = backlog.workflow_tasks,
+                    activity_backlog = backlog.activity_tasks,
+                    "task queue backlog"
+                );
+
+                let signal = if backlog.total() > backlog_settings.scale_up_threshold {
+                    Some(ScaleSignal::ScaleUp)
+                } else if backlog.total() < backlog_settings.scale_down_threshold {
+                    Some(ScaleSignal::ScaleDown)
+                } else {
+                    None
+                };
+
+                if let Some(signal) = signal.filter(|signal| last_signal != Some(*signal)) {
+                    on_threshold(signal, &backlog);
+                }
+                last_signal = signal;
+            }
+            Err(e) => tracing::warn!(error = ?e, %task_queue, "failed to check backlog"),
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -0,0 +1,122 @@
+//! Signals, cancels or terminates every workflow matching a visibility query, one client
+//! call per workflow. This fans out over the single-workflow helpers in [crate::client]
+//! rather than the server's batch operation API, so it works against any server version and
+//! reports each failure as it happens.
+
+use crate::client::{self, WorkflowSummary};
+use anyhow::Result;
+use futures::{stream, StreamExt, TryStreamExt};
+use temporal_sdk_core::{Client, RetryClient};
+
+/// What to do to each matching workflow.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchOperation {
+    Signal {
+        name: String,
+        input: serde_json::Value,
+    },
+    Cancel {
+        reason: String,
+    },
+    Terminate {
+        reason: String,
+    },
+}
+
+/// How far a batch has got. `succeeded + failed` reaches `matched` once it finishes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BatchProgress {
+    pub matched: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// Applies `operation` to every workflow matching `query`, with up to `concurrency` calls in
+/// flight. A failure for one workflow is logged and counted, and the batch carries on.
+/// `on_progress` is called after each workflow. With `dry_run` the matches are only counted.
+pub async fn run_batch(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    query: &str,
+    operation: &BatchOperation,
+    concurrency: usize,
+    dry_run: bool,
+    mut on_progress: impl FnMut(&BatchProgress),
+) -> Result<BatchProgress> {
+    // list everything up front, cancelling or terminating while paging would shift the
+    // results of a query on ExecutionStatus under us
+    let workflows: Vec<WorkflowSummary> = client::list_all_workflows(client, namespace, query, 100)
+        .try_collect()
+        .await?;
+
+    let mut progress = BatchProgress {
+        matched: workflows.len(),
+        ..Default::default()
+    };
+    if dry_run {
+        on_progress(&progress);
+        return Ok(progress);
+    }
+
+    let mut results = stream::iter(workflows)
+        .map(|workflow| async move {
+            let result = apply(client, namespace, &workflow, operation).await;
+            (workflow, result)
+        })
+        .buffer_unordered(concurrency.max(1));
+
+    while let Some((workflow, result)) = results.next().await {
+        match result {
+            Ok(()) => progress.succeeded += 1,
+            Err(err) => {
+                tracing::warn!(
+                    workflow_id = %workflow.workflow_id,
+                    run_id = %workflow.run_id,
+                    "batch operation failed: {:#}",
+                    err
+                );
+                progress.failed += 1;
+            }
+        }
+        on_progress(&progress);
+    }
+
+    Ok(progress)
+}
+
+async fn apply(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow: &WorkflowSummary,
+    operation: &BatchOperation,
+) -> Result<()> {
+    let run_id = Some(workflow.run_id.as_str());
+
+    match operation {
+        BatchOperation::Signal { name, input } => {
+            client::signal_workflow(
+                client,
+                namespace,
+                &workflow.workflow_id,
+                run_id,
+                name,
+                input,
+            )
+            .await
+        }
+        BatchOperation::Cancel { reason } => {
+            client::cancel_workflow(client, namespace, &workflow.workflow_id, run_id, reason).await
+        }
+        BatchOperation::Terminate { reason } => {
+            client::terminate_workflow(
+                client,
+                namespace,
+                &workflow.workflow_id,
+                run_id,
+                reason,
+                None,
+            )
+            .await
+        }
+    }
+}
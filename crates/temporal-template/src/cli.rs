@@ -0,0 +1,765 @@
+use crate::batch::BatchOperation;
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+
+const USAGE: &str = "usage: temporal-template [replay <history>... \
+    | register-namespace <name> <retention days> [<description>] \
+    | describe-namespace [<name>] \
+    | update-namespace <name> [--retention-days <days>] [--description <description>] \
+    | export-history <workflow id> <path> [<run id>] | describe-task-queue [<task queue>] \
+    | execute-workflow <workflow type> <input json> [<workflow id>] \
+    | describe-workflow <workflow id> [<run id>] | list-workflows [<query>] \
+    | signal-workflow <workflow id> <signal> <json> \
+    | signal-with-start <workflow type> <workflow id> <input json> <signal> <signal json> \
+    | cancel-workflow <workflow id> [<reason>] \
+    | reset-workflow <workflow id> <run id> <event id> <reason> \
+    | terminate-workflow <workflow id> <reason> [<details json>] \
+    | create-schedule <schedule id> <file> | describe-schedule <schedule id> \
+    | update-schedule <schedule id> <file> | delete-schedule <schedule id> \
+    | batch (signal <query> <signal> <json> | cancel <query> [<reason>] \
+    | terminate <query> <reason>) [--dry-run] \
+    | complete-activity <token> <json> | fail-activity <token> <message>]";
+
+/// What the binary was asked to do. With no arguments it runs the worker.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    RunWorker,
+    /// `replay <history>...` checks workflow changes against exported histories.
+    Replay {
+        paths: Vec<String>,
+    },
+    /// `register-namespace <name> <retention days> [<description>]` creates a namespace.
+    RegisterNamespace {
+        namespace: String,
+        retention: Duration,
+        description: String,
+    },
+    /// `describe-namespace [<name>]` prints a namespace's settings, the configured one by
+    /// default.
+    DescribeNamespace {
+        namespace: Option<String>,
+    },
+    /// `update-namespace <name> [--retention-days <days>] [--description <description>]`
+    /// changes whichever of a namespace's retention and description are given.
+    UpdateNamespace {
+        namespace: String,
+        retention: Option<Duration>,
+        description: Option<String>,
+    },
+    /// `export-history <workflow id> <path> [<run id>]` saves a workflow's history for
+    /// `replay`, from its latest run unless a run id is given.
+    ExportHistory {
+        workflow_id: String,
+        path: String,
+        run_id: Option<String>,
+    },
+    /// `describe-task-queue [<task queue>]` lists the workers polling a task queue, the
+    /// configured one by default.
+    DescribeTaskQueue {
+        task_queue: Option<String>,
+    },
+    /// `execute-workflow <workflow type> <input json> [<workflow id>]` starts a workflow on
+    /// the configured task queue and waits for its result.
+    ExecuteWorkflow {
+        workflow_type: String,
+        input: serde_json::Value,
+        workflow_id: Option<String>,
+    },
+    /// `describe-workflow <workflow id> [<run id>]` shows a workflow's status and pending
+    /// activities, for its latest run unless a run id is given.
+    DescribeWorkflow {
+        workflow_id: String,
+        run_id: Option<String>,
+    },
+    /// `list-workflows [<query>]` lists the workflows matching a visibility query, or every
+    /// workflow.
+    ListWorkflows {
+        query: String,
+    },
+    /// `signal-workflow <workflow id> <signal> <json>` signals the latest run of a workflow.
+    SignalWorkflow {
+        workflow_id: String,
+        signal_name: String,
+        input: serde_json::Value,
+    },
+    /// `signal-with-start <workflow type> <workflow id> <input json> <signal> <signal json>`
+    /// signals a workflow, starting it on the configured task queue if it isn't running.
+    SignalWithStart {
+        workflow_type: String,
+        workflow_id: String,
+        input: serde_json::Value,
+        signal_name: String,
+        signal_input: serde_json::Value,
+    },
+    /// `cancel-workflow <workflow id> [<reason>]` asks the latest run of a workflow to
+    /// cancel.
+    CancelWorkflow {
+        workflow_id: String,
+        reason: String,
+    },
+    /// `terminate-workflow <workflow id> <reason> [<details json>]` stops the latest run of
+    /// a workflow immediately.
+    TerminateWorkflow {
+        workflow_id: String,
+        reason: String,
+        details: Option<serde_json::Value>,
+    },
+    /// `reset-workflow <workflow id> <run id> <event id> <reason>` reruns a workflow from
+    /// the workflow task that finished at an event in its history.
+    ResetWorkflow {
+        workflow_id: String,
+        run_id: String,
+        event_id: i64,
+        reason: String,
+    },
+    /// `create-schedule <schedule id> <file>` creates a schedule from a definition like
+    /// `schedule.example.yaml`.
+    CreateSchedule {
+        schedule_id: String,
+        path: String,
+    },
+    /// `describe-schedule <schedule id>` shows a schedule's state and next runs.
+    DescribeSchedule {
+        schedule_id: String,
+    },
+    /// `update-schedule <schedule id> <file>` replaces a schedule's definition.
+    UpdateSchedule {
+        schedule_id: String,
+        path: String,
+    },
+    /// `delete-schedule <schedule id>` deletes a schedule, leaving its workflows running.
+    DeleteSchedule {
+        schedule_id: String,
+    },
+    /// `batch <operation> <query> ... [--dry-run]` signals, cancels or terminates every
+    /// workflow matching a visibility query. `--dry-run` only counts the matches.
+    Batch {
+        query: String,
+        operation: BatchOperation,
+        dry_run: bool,
+    },
+    /// `complete-activity <token> <json>` finishes an activity that is waiting to be
+    /// completed by task token, e.g. approval_activity.
+    CompleteActivity {
+        token: String,
+        result: serde_json::Value,
+    },
+    /// `fail-activity <token> <message>` fails an activity that is waiting to be completed by
+    /// task token.
+    FailActivity {
+        token: String,
+        message: String,
+    },
+}
+
+impl Command {
+    /// Parses the arguments after the binary name, rejecting unknown commands and wrong
+    /// argument counts rather than falling back to running the worker.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let (command, rest) = match args.split_first() {
+            Some(split) => split,
+            None => return Ok(Self::RunWorker),
+        };
+
+        Ok(match (command.as_str(), rest) {
+            ("replay", paths) if !paths.is_empty() => Self::Replay {
+                paths: paths.to_vec(),
+            },
+            ("register-namespace", [namespace, retention_days, description @ ..])
+                if description.len() <= 1 =>
+            {
+                Self::RegisterNamespace {
+                    namespace: namespace.clone(),
+                    retention: parse_days(retention_days)?,
+                    description: description.first().cloned().unwrap_or_default(),
+                }
+            }
+            ("describe-namespace", namespace @ ([] | [_])) => Self::DescribeNamespace {
+                namespace: namespace.first().cloned(),
+            },
+            ("update-namespace", [namespace, options @ ..]) if !options.is_empty() => {
+                let mut retention = None;
+                let mut description = None;
+                for option in options.chunks(2) {
+                    match option {
+                        [name, days] if name == "--retention-days" && retention.is_none() => {
+                            retention = Some(parse_days(days)?)
+                        }
+                        [name, text] if name == "--description" && description.is_none() => {
+                            description = Some(text.clone())
+                        }
+                        _ => bail!("Invalid arguments {:?}\n{}", args, USAGE),
+                    }
+                }
+
+                Self::UpdateNamespace {
+                    namespace: namespace.clone(),
+                    retention,
+                    description,
+                }
+            }
+            ("export-history", [workflow_id, path, run_id @ ..]) if run_id.len() <= 1 => {
+                Self::ExportHistory {
+                    workflow_id: workflow_id.clone(),
+                    path: path.clone(),
+                    run_id: run_id.first().cloned(),
+                }
+            }
+            ("describe-task-queue", task_queue @ ([] | [_])) => Self::DescribeTaskQueue {
+                task_queue: task_queue.first().cloned(),
+            },
+            ("execute-workflow", [workflow_type, input, workflow_id @ ..])
+                if workflow_id.len() <= 1 =>
+            {
+                Self::ExecuteWorkflow {
+                    workflow_type: workflow_type.clone(),
+                    input: serde_json::from_str(input)
+                        .context("execute-workflow input must be json")?,
+                    workflow_id: workflow_id.first().cloned(),
+                }
+            }
+            ("describe-workflow", [workflow_id, run_id @ ..]) if run_id.len() <= 1 => {
+                Self::DescribeWorkflow {
+                    workflow_id: workflow_id.clone(),
+                    run_id: run_id.first().cloned(),
+                }
+            }
+            ("list-workflows", query @ ([] | [_])) => Self::ListWorkflows {
+                query: query.first().cloned().unwrap_or_default(),
+            },
+            ("signal-workflow", [workflow_id, signal_name, input]) => Self::SignalWorkflow {
+                workflow_id: workflow_id.clone(),
+                signal_name: signal_name.clone(),
+                input: serde_json::from_str(input).context("signal-workflow input must be json")?,
+            },
+            (
+                "signal-with-start",
+                [workflow_type, workflow_id, input, signal_name, signal_input],
+            ) => Self::SignalWithStart {
+                workflow_type: workflow_type.clone(),
+                workflow_id: workflow_id.clone(),
+                input: serde_json::from_str(input)
+                    .context("signal-with-start input must be json")?,
+                signal_name: signal_name.clone(),
+                signal_input: serde_json::from_str(signal_input)
+                    .context("signal-with-start signal input must be json")?,
+            },
+            ("cancel-workflow", [workflow_id, reason @ ..]) if reason.len() <= 1 => {
+                Self::CancelWorkflow {
+                    workflow_id: workflow_id.clone(),
+                    reason: reason.first().cloned().unwrap_or_default(),
+                }
+            }
+            ("terminate-workflow", [workflow_id, reason, details @ ..]) if details.len() <= 1 => {
+                Self::TerminateWorkflow {
+                    workflow_id: workflow_id.clone(),
+                    reason: reason.clone(),
+                    details: details
+                        .first()
+                        .map(|details| serde_json::from_str(details))
+                        .transpose()
+                        .context("terminate-workflow details must be json")?,
+                }
+            }
+            ("reset-workflow", [workflow_id, run_id, event_id, reason]) => Self::ResetWorkflow {
+                workflow_id: workflow_id.clone(),
+                run_id: run_id.clone(),
+                event_id: event_id
+                    .parse()
+                    .with_context(|| format!("Invalid event id {}", event_id))?,
+                reason: reason.clone(),
+            },
+            ("create-schedule", [schedule_id, path]) => Self::CreateSchedule {
+                schedule_id: schedule_id.clone(),
+                path: path.clone(),
+            },
+            ("describe-schedule", [schedule_id]) => Self::DescribeSchedule {
+                schedule_id: schedule_id.clone(),
+            },
+            ("update-schedule", [schedule_id, path]) => Self::UpdateSchedule {
+                schedule_id: schedule_id.clone(),
+                path: path.clone(),
+            },
+            ("delete-schedule", [schedule_id]) => Self::DeleteSchedule {
+                schedule_id: schedule_id.clone(),
+            },
+            ("batch", [operation, query, rest @ ..]) => {
+                let (dry_run, rest) = match rest {
+                    [rest @ .., flag] if flag == "--dry-run" => (true, rest),
+                    rest => (false, rest),
+                };
+                let operation = match (operation.as_str(), rest) {
+                    ("signal", [name, input]) => BatchOperation::Signal {
+                        name: name.clone(),
+                        input: serde_json::from_str(input)
+                            .context("batch signal input must be json")?,
+                    },
+                    ("cancel", reason @ ([] | [_])) => BatchOperation::Cancel {
+                        reason: reason.first().cloned().unwrap_or_default(),
+                    },
+                    ("terminate", [reason]) => BatchOperation::Terminate {
+                        reason: reason.clone(),
+                    },
+                    _ => bail!("Invalid arguments {:?}\n{}", args, USAGE),
+                };
+
+                Self::Batch {
+                    query: query.clone(),
+                    operation,
+                    dry_run,
+                }
+            }
+            ("complete-activity", [token, result]) => Self::CompleteActivity {
+                token: token.clone(),
+                result: serde_json::from_str(result)
+                    .context("complete-activity result must be json")?,
+            },
+            ("fail-activity", [token, message]) => Self::FailActivity {
+                token: token.clone(),
+                message: message.clone(),
+            },
+            _ => bail!("Invalid arguments {:?}\n{}", args, USAGE),
+        })
+    }
+}
+
+fn parse_days(days: &str) -> Result<Duration> {
+    let secs = days
+        .parse::<u64>()
+        .with_context(|| format!("Invalid number of days {}", days))?
+        .checked_mul(24 * 60 * 60)
+        .with_context(|| format!("Too many days {}", days))?;
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Command::from_args(&args)
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn runs_the_worker_without_arguments() {
+        assert_eq!(parse(&[]).unwrap(), Command::RunWorker);
+    }
+
+    #[test]
+    fn parses_namespace_commands() {
+        assert_eq!(
+            parse(&["register-namespace", "ns", "3"]).unwrap(),
+            Command::RegisterNamespace {
+                namespace: "ns".to_string(),
+                retention: 3 * DAY,
+                description: String::new(),
+            }
+        );
+        assert_eq!(
+            parse(&["register-namespace", "ns", "3", "orders"]).unwrap(),
+            Command::RegisterNamespace {
+                namespace: "ns".to_string(),
+                retention: 3 * DAY,
+                description: "orders".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&["describe-namespace"]).unwrap(),
+            Command::DescribeNamespace { namespace: None }
+        );
+        assert_eq!(
+            parse(&["describe-namespace", "ns"]).unwrap(),
+            Command::DescribeNamespace {
+                namespace: Some("ns".to_string())
+            }
+        );
+        assert_eq!(
+            parse(&["update-namespace", "ns", "--description", "orders"]).unwrap(),
+            Command::UpdateNamespace {
+                namespace: "ns".to_string(),
+                retention: None,
+                description: Some("orders".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "update-namespace",
+                "ns",
+                "--retention-days",
+                "3",
+                "--description",
+                "orders"
+            ])
+            .unwrap(),
+            Command::UpdateNamespace {
+                namespace: "ns".to_string(),
+                retention: Some(3 * DAY),
+                description: Some("orders".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_history_and_task_queue_commands() {
+        assert_eq!(
+            parse(&["replay", "a.bin", "b.bin"]).unwrap(),
+            Command::Replay {
+                paths: strings(&["a.bin", "b.bin"])
+            }
+        );
+        assert_eq!(
+            parse(&["export-history", "wf", "wf.bin"]).unwrap(),
+            Command::ExportHistory {
+                workflow_id: "wf".to_string(),
+                path: "wf.bin".to_string(),
+                run_id: None,
+            }
+        );
+        assert_eq!(
+            parse(&["export-history", "wf", "wf.bin", "run"]).unwrap(),
+            Command::ExportHistory {
+                workflow_id: "wf".to_string(),
+                path: "wf.bin".to_string(),
+                run_id: Some("run".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&["describe-task-queue"]).unwrap(),
+            Command::DescribeTaskQueue { task_queue: None }
+        );
+        assert_eq!(
+            parse(&["describe-task-queue", "tq"]).unwrap(),
+            Command::DescribeTaskQueue {
+                task_queue: Some("tq".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn parses_workflow_commands() {
+        assert_eq!(
+            parse(&["execute-workflow", "dsl_workflow", r#"{"steps":[]}"#]).unwrap(),
+            Command::ExecuteWorkflow {
+                workflow_type: "dsl_workflow".to_string(),
+                input: json!({ "steps": [] }),
+                workflow_id: None,
+            }
+        );
+        assert_eq!(
+            parse(&["execute-workflow", "dsl_workflow", "null", "wf"]).unwrap(),
+            Command::ExecuteWorkflow {
+                workflow_type: "dsl_workflow".to_string(),
+                input: json!(null),
+                workflow_id: Some("wf".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&["describe-workflow", "wf"]).unwrap(),
+            Command::DescribeWorkflow {
+                workflow_id: "wf".to_string(),
+                run_id: None,
+            }
+        );
+        assert_eq!(
+            parse(&["describe-workflow", "wf", "run"]).unwrap(),
+            Command::DescribeWorkflow {
+                workflow_id: "wf".to_string(),
+                run_id: Some("run".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(&["list-workflows"]).unwrap(),
+            Command::ListWorkflows {
+                query: String::new()
+            }
+        );
+        assert_eq!(
+            parse(&["list-workflows", "WorkflowType = 'dsl_workflow'"]).unwrap(),
+            Command::ListWorkflows {
+                query: "WorkflowType = 'dsl_workflow'".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&["signal-workflow", "wf", "approve", "true"]).unwrap(),
+            Command::SignalWorkflow {
+                workflow_id: "wf".to_string(),
+                signal_name: "approve".to_string(),
+                input: json!(true),
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "signal-with-start",
+                "dsl_workflow",
+                "wf",
+                "{}",
+                "approve",
+                "1"
+            ])
+            .unwrap(),
+            Command::SignalWithStart {
+                workflow_type: "dsl_workflow".to_string(),
+                workflow_id: "wf".to_string(),
+                input: json!({}),
+                signal_name: "approve".to_string(),
+                signal_input: json!(1),
+            }
+        );
+        assert_eq!(
+            parse(&["cancel-workflow", "wf"]).unwrap(),
+            Command::CancelWorkflow {
+                workflow_id: "wf".to_string(),
+                reason: String::new(),
+            }
+        );
+        assert_eq!(
+            parse(&["cancel-workflow", "wf", "not needed"]).unwrap(),
+            Command::CancelWorkflow {
+                workflow_id: "wf".to_string(),
+                reason: "not needed".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&["terminate-workflow", "wf", "stuck"]).unwrap(),
+            Command::TerminateWorkflow {
+                workflow_id: "wf".to_string(),
+                reason: "stuck".to_string(),
+                details: None,
+            }
+        );
+        assert_eq!(
+            parse(&["terminate-workflow", "wf", "stuck", r#"{"ticket":1}"#]).unwrap(),
+            Command::TerminateWorkflow {
+                workflow_id: "wf".to_string(),
+                reason: "stuck".to_string(),
+                details: Some(json!({ "ticket": 1 })),
+            }
+        );
+        assert_eq!(
+            parse(&["reset-workflow", "wf", "run", "4", "bad deploy"]).unwrap(),
+            Command::ResetWorkflow {
+                workflow_id: "wf".to_string(),
+                run_id: "run".to_string(),
+                event_id: 4,
+                reason: "bad deploy".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_schedule_commands() {
+        assert_eq!(
+            parse(&["create-schedule", "daily", "schedule.yaml"]).unwrap(),
+            Command::CreateSchedule {
+                schedule_id: "daily".to_string(),
+                path: "schedule.yaml".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&["describe-schedule", "daily"]).unwrap(),
+            Command::DescribeSchedule {
+                schedule_id: "daily".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&["update-schedule", "daily", "schedule.yaml"]).unwrap(),
+            Command::UpdateSchedule {
+                schedule_id: "daily".to_string(),
+                path: "schedule.yaml".to_string(),
+            }
+        );
+        assert_eq!(
+            parse(&["delete-schedule", "daily"]).unwrap(),
+            Command::DeleteSchedule {
+                schedule_id: "daily".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_batch_commands() {
+        assert_eq!(
+            parse(&["batch", "signal", "q", "approve", "true"]).unwrap(),
+            Command::Batch {
+                query: "q".to_string(),
+                operation: BatchOperation::Signal {
+                    name: "approve".to_string(),
+                    input: json!(true),
+                },
+                dry_run: false,
+            }
+        );
+        assert_eq!(
+            parse(&["batch", "cancel", "q", "--dry-run"]).unwrap(),
+            Command::Batch {
+                query: "q".to_string(),
+                operation: BatchOperation::Cancel {
+                    reason: String::new()
+                },
+                dry_run: true,
+            }
+        );
+        assert_eq!(
+            parse(&["batch", "terminate", "q", "stuck", "--dry-run"]).unwrap(),
+            Command::Batch {
+                query: "q".to_string(),
+                operation: BatchOperation::Terminate {
+                    reason: "stuck".to_string()
+                },
+                dry_run: true,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_activity_commands() {
+        assert_eq!(
+            parse(&["complete-activity", "token", r#""approved""#]).unwrap(),
+            Command::CompleteActivity {
+                token: "token".to_string(),
+                result: json!("approved"),
+            }
+        );
+        assert_eq!(
+            parse(&["fail-activity", "token", "rejected"]).unwrap(),
+            Command::FailActivity {
+                token: "token".to_string(),
+                message: "rejected".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_commands() {
+        assert!(parse(&["run"]).is_err());
+        assert!(parse(&["--help"]).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_arguments() {
+        for args in [
+            &["replay"][..],
+            &["register-namespace", "ns"],
+            &["update-namespace", "ns"],
+            &["update-namespace", "ns", "--retention-days"],
+            &["export-history", "wf"],
+            &["execute-workflow", "dsl_workflow"],
+            &["describe-workflow"],
+            &["signal-workflow", "wf", "approve"],
+            &["signal-with-start", "dsl_workflow", "wf", "{}", "approve"],
+            &["cancel-workflow"],
+            &["terminate-workflow", "wf"],
+            &["reset-workflow", "wf", "run", "4"],
+            &["create-schedule", "daily"],
+            &["describe-schedule"],
+            &["update-schedule", "daily"],
+            &["delete-schedule"],
+            &["batch", "signal", "q", "approve"],
+            &["batch", "terminate", "q"],
+            &["batch", "cancel"],
+            &["complete-activity", "token"],
+            &["fail-activity", "token"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn rejects_extra_arguments() {
+        for args in [
+            &["register-namespace", "ns", "3", "orders", "extra"][..],
+            &["describe-namespace", "ns", "extra"],
+            &[
+                "update-namespace",
+                "ns",
+                "--description",
+                "a",
+                "--description",
+                "b",
+            ],
+            &["update-namespace", "ns", "--owner", "me"],
+            &["export-history", "wf", "wf.bin", "run", "extra"],
+            &["describe-task-queue", "tq", "extra"],
+            &["execute-workflow", "dsl_workflow", "{}", "wf", "extra"],
+            &["describe-workflow", "wf", "run", "extra"],
+            &["list-workflows", "q", "extra"],
+            &["signal-workflow", "wf", "approve", "true", "extra"],
+            &[
+                "signal-with-start",
+                "dsl_workflow",
+                "wf",
+                "{}",
+                "approve",
+                "1",
+                "extra",
+            ],
+            &["cancel-workflow", "wf", "reason", "extra"],
+            &["terminate-workflow", "wf", "stuck", "{}", "extra"],
+            &["reset-workflow", "wf", "run", "4", "bad deploy", "extra"],
+            &["create-schedule", "daily", "schedule.yaml", "extra"],
+            &["describe-schedule", "daily", "extra"],
+            &["update-schedule", "daily", "schedule.yaml", "extra"],
+            &["delete-schedule", "daily", "extra"],
+            &["batch", "cancel", "q", "reason", "extra"],
+            &["batch", "pause", "q"],
+            &["complete-activity", "token", "{}", "extra"],
+            &["fail-activity", "token", "rejected", "extra"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_numbers() {
+        for args in [
+            &["register-namespace", "ns", "three"][..],
+            &["register-namespace", "ns", "-1"],
+            &["update-namespace", "ns", "--retention-days", "1.5"],
+            &["reset-workflow", "wf", "run", "four", "bad deploy"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+
+        let err = parse(&["register-namespace", "ns", &u64::MAX.to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Too many days"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_bad_json() {
+        for args in [
+            &["execute-workflow", "dsl_workflow", "{steps"][..],
+            &["signal-workflow", "wf", "approve", "yes"],
+            &[
+                "signal-with-start",
+                "dsl_workflow",
+                "wf",
+                "{",
+                "approve",
+                "1",
+            ],
+            &[
+                "signal-with-start",
+                "dsl_workflow",
+                "wf",
+                "{}",
+                "approve",
+                "x",
+            ],
+            &["terminate-workflow", "wf", "stuck", "{"],
+            &["batch", "signal", "q", "approve", "yes"],
+            &["complete-activity", "token", "approved"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
+}
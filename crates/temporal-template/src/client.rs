@@ -0,0 +1,1054 @@
+use crate::{
+    client_interceptor::{intercept, ClientCall},
+    env_vars::{get_env_or_default, get_env_parsed},
+    secrets,
+};
+use anyhow::{bail, Context, Result};
+use futures::{stream, Stream, TryStreamExt};
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use temporal_client::WorkflowService;
+use temporal_sdk::sdk_client_options;
+use temporal_sdk_core::{
+    protos::coresdk::AsJsonPayloadExt, Client, ClientOptions, ClientTlsConfig, RetryClient,
+    RetryConfig, TlsConfig, Url, WorkflowClientTrait,
+};
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::{Payloads, WorkflowExecution, WorkflowType},
+    enums::v1::{
+        HistoryEventFilterType, PendingActivityState, TaskQueueKind, TaskQueueType,
+        WorkflowExecutionStatus,
+    },
+    history::v1::{history_event::Attributes, History, HistoryEvent},
+    namespace::v1::{NamespaceConfig, UpdateNamespaceInfo},
+    taskqueue::v1::TaskQueue,
+    workflow::v1::{PendingActivityInfo, WorkflowExecutionInfo},
+    workflowservice::v1::{
+        DescribeNamespaceRequest, DescribeTaskQueueRequest, DescribeWorkflowExecutionRequest,
+        GetSystemInfoRequest, GetWorkflowExecutionHistoryRequest, ListWorkflowExecutionsRequest,
+        RegisterNamespaceRequest, RequestCancelWorkflowExecutionRequest,
+        ResetWorkflowExecutionRequest, SignalWithStartWorkflowExecutionRequest,
+        SignalWorkflowExecutionRequest, StartWorkflowExecutionRequest,
+        TerminateWorkflowExecutionRequest, UpdateNamespaceRequest,
+    },
+};
+
+/// Connects to Temporal with `namespace` as the client's default namespace, so the returned
+/// handle can be used through [WorkflowClientTrait] as well as to back a worker.
+///
+/// `urls` are tried in order (e.g. primary then DR cluster). When one
+/// can't be connected to, `on_failover` is called with the failed url, the url about to be
+/// tried next, and the connection error. Failover only happens while connecting; an
+/// established client stays on its url.
+pub async fn connect_to_namespace(
+    urls: &[Url],
+    namespace: &str,
+    on_failover: impl Fn(&Url, &Url, &anyhow::Error),
+) -> Result<RetryClient<Client>> {
+    let mut urls = urls.iter().peekable();
+
+    while let Some(url) = urls.next() {
+        let connected = async {
+            build_client_options(url)?
+                .connect(namespace, None, api_key_headers(namespace)?)
+                .await
+                .map_err(anyhow::Error::from)
+        }
+        .await;
+
+        match connected {
+            Ok(client) => {
+                tracing::info!(namespace = client.namespace(), %url, "connected to temporal");
+                return Ok(client);
+            }
+            Err(e) => match urls.peek() {
+                Some(next_url) => on_failover(url, next_url, &e),
+                None => return Err(e.context(format!("Failed to connect to {}", url))),
+            },
+        }
+    }
+
+    bail!("No Temporal urls configured")
+}
+
+/// A namespace's settings, see [describe_namespace].
+#[derive(Debug, Clone)]
+pub struct NamespaceDescription {
+    pub name: String,
+    pub description: String,
+    /// How long closed workflows' histories are kept.
+    pub retention: Option<Duration>,
+}
+
+/// Registers `namespace`, keeping closed workflows' histories for `retention`.
+pub async fn register_namespace(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    retention: Duration,
+    description: &str,
+) -> Result<()> {
+    client
+        .clone()
+        .register_namespace(RegisterNamespaceRequest {
+            namespace: namespace.to_string(),
+            description: description.to_string(),
+            workflow_execution_retention_period: Some(retention.try_into()?),
+            ..Default::default()
+        })
+        .await
+        .with_context(|| format!("Failed to register namespace {}", namespace))?;
+
+    Ok(())
+}
+
+pub async fn describe_namespace(
+    client: &RetryClient<Client>,
+    namespace: &str,
+) -> Result<NamespaceDescription> {
+    let response = client
+        .clone()
+        .describe_namespace(DescribeNamespaceRequest {
+            namespace: namespace.to_string(),
+            ..Default::default()
+        })
+        .await
+        .with_context(|| format!("Failed to describe namespace {}", namespace))?
+        .into_inner();
+
+    let info = response.namespace_info.unwrap_or_default();
+    Ok(NamespaceDescription {
+        name: info.name,
+        description: info.description,
+        retention: response
+            .config
+            .and_then(|config| config.workflow_execution_retention_ttl)
+            .and_then(|ttl| ttl.try_into().ok()),
+    })
+}
+
+/// Changes `namespace`'s retention and description. Settings passed as `None` are left as
+/// they are.
+pub async fn update_namespace(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    retention: Option<Duration>,
+    description: Option<&str>,
+) -> Result<()> {
+    let config = match retention {
+        Some(retention) => Some(NamespaceConfig {
+            workflow_execution_retention_ttl: Some(retention.try_into()?),
+            ..Default::default()
+        }),
+        None => None,
+    };
+
+    client
+        .clone()
+        .update_namespace(UpdateNamespaceRequest {
+            namespace: namespace.to_string(),
+            update_info: description.map(|description| UpdateNamespaceInfo {
+                description: description.to_string(),
+                ..Default::default()
+            }),
+            config,
+            ..Default::default()
+        })
+        .await
+        .with_context(|| format!("Failed to update namespace {}", namespace))?;
+
+    Ok(())
+}
+
+/// Result of [check_temporal_health].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemporalHealth {
+    Serving,
+    Unavailable(String),
+    TimedOut,
+}
+
+/// Calls `GetSystemInfo` on an established client to confirm Temporal is still answering,
+/// giving up after `timeout`. Needs no namespace permissions.
+pub async fn check_temporal_health(
+    client: &RetryClient<Client>,
+    timeout: Duration,
+) -> TemporalHealth {
+    let mut client = client.clone();
+    let system_info = client.get_system_info(GetSystemInfoRequest::default());
+
+    match tokio::time::timeout(timeout, system_info).await {
+        Ok(Ok(_)) => TemporalHealth::Serving,
+        Ok(Err(status)) => TemporalHealth::Unavailable(status.to_string()),
+        Err(_) => TemporalHealth::TimedOut,
+    }
+}
+
+/// A worker polling a task queue, see [describe_task_queue].
+#[derive(Debug, Clone)]
+pub struct Poller {
+    pub identity: String,
+    pub last_access_time: Option<SystemTime>,
+    /// Rate limit the poller asked for, in tasks per second.
+    pub rate_per_second: f64,
+}
+
+/// Who is polling a task queue and how much work is waiting on it.
+#[derive(Debug, Clone, Default)]
+pub struct TaskQueueDescription {
+    pub workflow_pollers: Vec<Poller>,
+    pub activity_pollers: Vec<Poller>,
+    /// Approximate number of workflow tasks waiting.
+    pub workflow_backlog: i64,
+    /// Approximate number of activity tasks waiting.
+    pub activity_backlog: i64,
+}
+
+/// Describes `task_queue`'s workflow and activity sides, to check that workers are actually
+/// polling it. Pollers that haven't polled for a few minutes drop out of the list.
+pub async fn describe_task_queue(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    task_queue: &str,
+) -> Result<TaskQueueDescription> {
+    let mut description = TaskQueueDescription::default();
+
+    for task_queue_type in [TaskQueueType::Workflow, TaskQueueType::Activity] {
+        let response = client
+            .clone()
+            .describe_task_queue(DescribeTaskQueueRequest {
+                namespace: namespace.to_string(),
+                task_queue: Some(TaskQueue {
+                    name: task_queue.to_string(),
+                    kind: TaskQueueKind::Normal as i32,
+                }),
+                task_queue_type: task_queue_type as i32,
+                include_task_queue_status: true,
+            })
+            .await
+            .with_context(|| format!("Failed to describe task queue {}", task_queue))?
+            .into_inner();
+
+        let pollers = response
+            .pollers
+            .into_iter()
+            .map(|poller| Poller {
+                identity: poller.identity,
+                last_access_time: poller.last_access_time.and_then(|t| t.try_into().ok()),
+                rate_per_second: poller.rate_per_second,
+            })
+            .collect();
+        let backlog = response
+            .task_queue_status
+            .map(|status| status.backlog_count_hint)
+            .unwrap_or_default();
+
+        match task_queue_type {
+            TaskQueueType::Workflow => {
+                description.workflow_pollers = pollers;
+                description.workflow_backlog = backlog;
+            }
+            _ => {
+                description.activity_pollers = pollers;
+                description.activity_backlog = backlog;
+            }
+        }
+    }
+
+    Ok(description)
+}
+
+/// The main facts about a workflow run, see [describe_workflow] and [list_workflows].
+#[derive(Debug, Clone)]
+pub struct WorkflowSummary {
+    pub workflow_id: String,
+    pub run_id: String,
+    pub workflow_type: String,
+    pub status: WorkflowExecutionStatus,
+    pub task_queue: String,
+    pub start_time: Option<SystemTime>,
+    pub close_time: Option<SystemTime>,
+}
+
+impl From<WorkflowExecutionInfo> for WorkflowSummary {
+    fn from(info: WorkflowExecutionInfo) -> Self {
+        let execution = info.execution.unwrap_or_default();
+
+        Self {
+            workflow_id: execution.workflow_id,
+            run_id: execution.run_id,
+            workflow_type: info.r#type.map(|t| t.name).unwrap_or_default(),
+            status: WorkflowExecutionStatus::from_i32(info.status)
+                .unwrap_or(WorkflowExecutionStatus::Unspecified),
+            task_queue: info.task_queue,
+            start_time: info.start_time.and_then(|t| t.try_into().ok()),
+            close_time: info.close_time.and_then(|t| t.try_into().ok()),
+        }
+    }
+}
+
+/// A workflow run and the activities it is waiting on, see [describe_workflow].
+#[derive(Debug, Clone)]
+pub struct WorkflowDescription {
+    pub summary: WorkflowSummary,
+    pub pending_activities: Vec<PendingActivity>,
+}
+
+/// An activity a workflow has scheduled that hasn't finished yet.
+#[derive(Debug, Clone)]
+pub struct PendingActivity {
+    pub activity_id: String,
+    pub activity_type: String,
+    pub state: PendingActivityState,
+    pub attempt: i32,
+    pub last_heartbeat_time: Option<SystemTime>,
+    /// Why the last attempt failed, if one did.
+    pub last_failure: Option<String>,
+}
+
+impl From<PendingActivityInfo> for PendingActivity {
+    fn from(info: PendingActivityInfo) -> Self {
+        Self {
+            activity_id: info.activity_id,
+            activity_type: info.activity_type.map(|t| t.name).unwrap_or_default(),
+            state: PendingActivityState::from_i32(info.state)
+                .unwrap_or(PendingActivityState::Unspecified),
+            attempt: info.attempt,
+            last_heartbeat_time: info.last_heartbeat_time.and_then(|t| t.try_into().ok()),
+            last_failure: info.last_failure.map(|failure| failure.message),
+        }
+    }
+}
+
+/// Describes a workflow run, or the latest run of `workflow_id` when `run_id` is `None`.
+pub async fn describe_workflow(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: Option<&str>,
+) -> Result<WorkflowDescription> {
+    let response = client
+        .clone()
+        .describe_workflow_execution(DescribeWorkflowExecutionRequest {
+            namespace: namespace.to_string(),
+            execution: Some(workflow_execution(workflow_id, run_id)),
+        })
+        .await
+        .with_context(|| format!("Failed to describe workflow {}", workflow_id))?
+        .into_inner();
+
+    Ok(WorkflowDescription {
+        summary: response.workflow_execution_info.unwrap_or_default().into(),
+        pending_activities: response
+            .pending_activities
+            .into_iter()
+            .map(PendingActivity::from)
+            .collect(),
+    })
+}
+
+/// One page of [list_workflows] results.
+#[derive(Debug, Clone, Default)]
+pub struct WorkflowPage {
+    pub workflows: Vec<WorkflowSummary>,
+    /// Pass to [list_workflows] for the next page. Empty on the last page.
+    pub next_page_token: Vec<u8>,
+}
+
+/// Lists workflows matching the visibility `query` (e.g. `WorkflowType = 'dsl_workflow' AND
+/// ExecutionStatus = 'Running'`), at most `page_size` at a time. Pass an empty `page_token`
+/// for the first page. An empty query matches every workflow.
+pub async fn list_workflows(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    query: &str,
+    page_size: i32,
+    page_token: Vec<u8>,
+) -> Result<WorkflowPage> {
+    let response = client
+        .clone()
+        .list_workflow_executions(ListWorkflowExecutionsRequest {
+            namespace: namespace.to_string(),
+            page_size,
+            next_page_token: page_token,
+            query: query.to_string(),
+        })
+        .await
+        .with_context(|| format!("Failed to list workflows matching {:?}", query))?
+        .into_inner();
+
+    Ok(WorkflowPage {
+        workflows: response
+            .executions
+            .into_iter()
+            .map(WorkflowSummary::from)
+            .collect(),
+        next_page_token: response.next_page_token,
+    })
+}
+
+/// Every workflow matching `query` (see [list_workflows]), fetching each following page as
+/// the stream is read.
+pub fn list_all_workflows<'a>(
+    client: &'a RetryClient<Client>,
+    namespace: &'a str,
+    query: &'a str,
+    page_size: i32,
+) -> impl Stream<Item = Result<WorkflowSummary>> + 'a {
+    // None once the last page has been fetched
+    stream::try_unfold(Some(vec![]), move |page_token| async move {
+        let page_token = match page_token {
+            Some(page_token) => page_token,
+            None => return Ok(None),
+        };
+
+        let page = list_workflows(client, namespace, query, page_size, page_token).await?;
+        let next_page_token = (!page.next_page_token.is_empty()).then_some(page.next_page_token);
+
+        Ok(Some((
+            stream::iter(page.workflows.into_iter().map(Ok)),
+            next_page_token,
+        )))
+    })
+    .try_flatten()
+}
+
+/// Fetches every event of a workflow run, or of the latest run of `workflow_id` when
+/// `run_id` is `None`, a page at a time. With `wait_for_close`, long-polls for new events
+/// until the run closes instead of returning what has happened so far.
+pub async fn get_workflow_history(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: Option<&str>,
+    wait_for_close: bool,
+) -> Result<History> {
+    let mut history = History::default();
+    let mut page_token = vec![];
+
+    loop {
+        let response = client
+            .clone()
+            .get_workflow_execution_history(GetWorkflowExecutionHistoryRequest {
+                namespace: namespace.to_string(),
+                execution: Some(workflow_execution(workflow_id, run_id)),
+                next_page_token: page_token,
+                wait_new_event: wait_for_close,
+                history_event_filter_type: HistoryEventFilterType::AllEvent as i32,
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to fetch history of {}", workflow_id))?
+            .into_inner();
+
+        history
+            .events
+            .extend(response.history.map(|page| page.events).unwrap_or_default());
+
+        // when waiting, the server keeps handing out tokens until the run has closed
+        if response.next_page_token.is_empty() {
+            return Ok(history);
+        }
+        page_token = response.next_page_token;
+    }
+}
+
+/// Starts `workflow_type` on `task_queue` with `input` as its json argument, returning the
+/// new run's id.
+pub async fn start_workflow<A: Serialize>(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    task_queue: &str,
+    workflow_id: &str,
+    workflow_type: &str,
+    input: &A,
+) -> Result<String> {
+    let call = ClientCall {
+        operation: "start",
+        namespace,
+        workflow_id,
+    };
+
+    intercept(call, async {
+        let response = client
+            .clone()
+            .start_workflow_execution(StartWorkflowExecutionRequest {
+                namespace: namespace.to_string(),
+                workflow_id: workflow_id.to_string(),
+                workflow_type: Some(WorkflowType {
+                    name: workflow_type.to_string(),
+                }),
+                task_queue: Some(TaskQueue {
+                    name: task_queue.to_string(),
+                    kind: TaskQueueKind::Normal as i32,
+                }),
+                input: Some(Payloads {
+                    payloads: vec![input.as_json_payload()?],
+                }),
+                identity: ClientIdentityConfig::from_env().identity,
+                request_id: Uuid::new_v4().to_string(),
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to start workflow {}", workflow_id))?
+            .into_inner();
+
+        Ok(response.run_id)
+    })
+    .await
+}
+
+/// Starts a workflow like [start_workflow] and waits for its result, see [workflow_result].
+pub async fn start_workflow_and_await_result<A: Serialize, T: DeserializeOwned>(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    task_queue: &str,
+    workflow_id: &str,
+    workflow_type: &str,
+    input: &A,
+) -> Result<T> {
+    let run_id = start_workflow(
+        client,
+        namespace,
+        task_queue,
+        workflow_id,
+        workflow_type,
+        input,
+    )
+    .await?;
+
+    workflow_result(client, namespace, workflow_id, &run_id).await
+}
+
+/// How a workflow run ended without completing, returned by [workflow_result]. Get it back
+/// from the [anyhow::Error] with `downcast_ref::<WorkflowError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkflowError {
+    /// The workflow returned an error, with its message.
+    Failed(String),
+    TimedOut,
+    Cancelled,
+    /// Terminated by an operator, with the reason given.
+    Terminated(String),
+}
+
+impl fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Failed(message) => write!(f, "Workflow failed: {}", message),
+            Self::TimedOut => write!(f, "Workflow timed out"),
+            Self::Cancelled => write!(f, "Workflow was cancelled"),
+            Self::Terminated(reason) => write!(f, "Workflow was terminated: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for WorkflowError {}
+
+/// Waits for a workflow run to close and decodes its json result, following runs that
+/// continued as new. A workflow without a result (all workflows at the pinned SDK) decodes
+/// as json `null`, e.g. into `()`. A run that didn't complete is reported as a
+/// [WorkflowError].
+pub async fn workflow_result<T: DeserializeOwned>(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: &str,
+) -> Result<T> {
+    let mut run_id = run_id.to_string();
+
+    loop {
+        let close_event = wait_for_close_event(client, namespace, workflow_id, &run_id).await?;
+
+        let error = match close_event.attributes {
+            Some(Attributes::WorkflowExecutionCompletedEventAttributes(completed)) => {
+                let result = match completed.result.and_then(|r| r.payloads.into_iter().next()) {
+                    Some(payload) => serde_json::from_slice(&payload.data),
+                    None => serde_json::from_value(serde_json::Value::Null),
+                };
+                return result
+                    .with_context(|| format!("Failed to decode result of {}", workflow_id));
+            }
+            Some(Attributes::WorkflowExecutionContinuedAsNewEventAttributes(continued)) => {
+                run_id = continued.new_execution_run_id;
+                continue;
+            }
+            Some(Attributes::WorkflowExecutionFailedEventAttributes(failed)) => {
+                WorkflowError::Failed(failed.failure.map(|f| f.message).unwrap_or_default())
+            }
+            Some(Attributes::WorkflowExecutionTimedOutEventAttributes(_)) => {
+                WorkflowError::TimedOut
+            }
+            Some(Attributes::WorkflowExecutionCanceledEventAttributes(_)) => {
+                WorkflowError::Cancelled
+            }
+            Some(Attributes::WorkflowExecutionTerminatedEventAttributes(terminated)) => {
+                WorkflowError::Terminated(terminated.reason)
+            }
+            other => bail!("Unexpected close event for {}: {:?}", workflow_id, other),
+        };
+
+        return Err(anyhow::Error::new(error).context(format!("Workflow {}", workflow_id)));
+    }
+}
+
+/// Long-polls a run's history for the event that closed it.
+async fn wait_for_close_event(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: &str,
+) -> Result<HistoryEvent> {
+    let mut page_token = vec![];
+
+    loop {
+        let response = client
+            .clone()
+            .get_workflow_execution_history(GetWorkflowExecutionHistoryRequest {
+                namespace: namespace.to_string(),
+                execution: Some(workflow_execution(workflow_id, Some(run_id))),
+                next_page_token: page_token,
+                wait_new_event: true,
+                history_event_filter_type: HistoryEventFilterType::CloseEvent as i32,
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to wait for {} to close", workflow_id))?
+            .into_inner();
+
+        let close_event = response
+            .history
+            .and_then(|history| history.events.into_iter().next());
+        if let Some(close_event) = close_event {
+            return Ok(close_event);
+        }
+        // the long poll timed out while the run was still open
+        page_token = response.next_page_token;
+    }
+}
+
+/// Sends `signal_name` with `input` as its json argument to a workflow run, or to the latest
+/// run of `workflow_id` when `run_id` is `None`.
+pub async fn signal_workflow<A: Serialize>(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: Option<&str>,
+    signal_name: &str,
+    input: &A,
+) -> Result<()> {
+    let call = ClientCall {
+        operation: "signal",
+        namespace,
+        workflow_id,
+    };
+
+    intercept(call, async {
+        client
+            .clone()
+            .signal_workflow_execution(SignalWorkflowExecutionRequest {
+                namespace: namespace.to_string(),
+                workflow_execution: Some(workflow_execution(workflow_id, run_id)),
+                signal_name: signal_name.to_string(),
+                input: Some(Payloads {
+                    payloads: vec![input.as_json_payload()?],
+                }),
+                identity: ClientIdentityConfig::from_env().identity,
+                request_id: Uuid::new_v4().to_string(),
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to signal {} to {}", signal_name, workflow_id))?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Sends `signal_name` to `workflow_id`, first starting it as `workflow_type` with
+/// `workflow_input` if it isn't running. Both happen in one call, so an event can't be lost
+/// between checking for the workflow and starting it. Returns the id of the run that got
+/// the signal.
+#[allow(clippy::too_many_arguments)]
+pub async fn signal_with_start_workflow<A: Serialize, S: Serialize>(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    task_queue: &str,
+    workflow_id: &str,
+    workflow_type: &str,
+    workflow_input: &A,
+    signal_name: &str,
+    signal_input: &S,
+) -> Result<String> {
+    let call = ClientCall {
+        operation: "signal_with_start",
+        namespace,
+        workflow_id,
+    };
+
+    intercept(call, async {
+        let response = client
+            .clone()
+            .signal_with_start_workflow_execution(SignalWithStartWorkflowExecutionRequest {
+                namespace: namespace.to_string(),
+                workflow_id: workflow_id.to_string(),
+                workflow_type: Some(WorkflowType {
+                    name: workflow_type.to_string(),
+                }),
+                task_queue: Some(TaskQueue {
+                    name: task_queue.to_string(),
+                    kind: TaskQueueKind::Normal as i32,
+                }),
+                input: Some(Payloads {
+                    payloads: vec![workflow_input.as_json_payload()?],
+                }),
+                signal_name: signal_name.to_string(),
+                signal_input: Some(Payloads {
+                    payloads: vec![signal_input.as_json_payload()?],
+                }),
+                identity: ClientIdentityConfig::from_env().identity,
+                request_id: Uuid::new_v4().to_string(),
+                ..Default::default()
+            })
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to signal {} to {} with start",
+                    signal_name, workflow_id
+                )
+            })?
+            .into_inner();
+
+        Ok(response.run_id)
+    })
+    .await
+}
+
+/// Asks a workflow to cancel. The workflow is told and can clean up before it finishes, so
+/// it may keep running for a while. The latest run is cancelled when `run_id` is `None`.
+pub async fn cancel_workflow(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: Option<&str>,
+    reason: &str,
+) -> Result<()> {
+    let call = ClientCall {
+        operation: "cancel",
+        namespace,
+        workflow_id,
+    };
+
+    intercept(call, async {
+        client
+            .clone()
+            .request_cancel_workflow_execution(RequestCancelWorkflowExecutionRequest {
+                namespace: namespace.to_string(),
+                workflow_execution: Some(workflow_execution(workflow_id, run_id)),
+                identity: ClientIdentityConfig::from_env().identity,
+                reason: reason.to_string(),
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to cancel workflow {}", workflow_id))?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Stops a workflow immediately, without running any of its cleanup. Prefer
+/// [cancel_workflow] unless the workflow is stuck or misbehaving. `details` are recorded as
+/// json in the termination event. The latest run is terminated when `run_id` is `None`.
+pub async fn terminate_workflow(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: Option<&str>,
+    reason: &str,
+    details: Option<&serde_json::Value>,
+) -> Result<()> {
+    let call = ClientCall {
+        operation: "terminate",
+        namespace,
+        workflow_id,
+    };
+
+    intercept(call, async {
+        let details = match details {
+            Some(details) => Some(Payloads {
+                payloads: vec![details.as_json_payload()?],
+            }),
+            None => None,
+        };
+
+        client
+            .clone()
+            .terminate_workflow_execution(TerminateWorkflowExecutionRequest {
+                namespace: namespace.to_string(),
+                workflow_execution: Some(workflow_execution(workflow_id, run_id)),
+                reason: reason.to_string(),
+                details,
+                identity: ClientIdentityConfig::from_env().identity,
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to terminate workflow {}", workflow_id))?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Starts a new run of a workflow from the point in `run_id`'s history just after
+/// `event_id`, which must be a workflow task completed, failed or timed out event. The old
+/// run is terminated and signals received after that point are applied again. Returns the
+/// new run's id. Used to rewind workflows past a bad deploy.
+pub async fn reset_workflow(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    workflow_id: &str,
+    run_id: &str,
+    event_id: i64,
+    reason: &str,
+) -> Result<String> {
+    let call = ClientCall {
+        operation: "reset",
+        namespace,
+        workflow_id,
+    };
+
+    intercept(call, async {
+        let response = client
+            .clone()
+            .reset_workflow_execution(ResetWorkflowExecutionRequest {
+                namespace: namespace.to_string(),
+                workflow_execution: Some(workflow_execution(workflow_id, Some(run_id))),
+                reason: reason.to_string(),
+                workflow_task_finish_event_id: event_id,
+                request_id: Uuid::new_v4().to_string(),
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to reset workflow {}", workflow_id))?
+            .into_inner();
+
+        Ok(response.run_id)
+    })
+    .await
+}
+
+/// A workflow run, or the latest run of `workflow_id` when `run_id` is `None`.
+fn workflow_execution(workflow_id: &str, run_id: Option<&str>) -> WorkflowExecution {
+    WorkflowExecution {
+        workflow_id: workflow_id.to_string(),
+        run_id: run_id.unwrap_or_default().to_string(),
+    }
+}
+
+/// Client options for the worker's connection to the Temporal frontend at `url`.
+///
+/// TLS is enabled when the url uses `https`. Setting any of the `TEMPORAL_TLS_*` variables
+/// with an `http` url is an error rather than silently switching transports.
+pub fn build_client_options(url: &Url) -> Result<ClientOptions> {
+    let identity = ClientIdentityConfig::from_env();
+
+    let mut options = sdk_client_options(url.clone())
+        .identity(identity.identity)
+        .client_name(identity.client_name)
+        .client_version(identity.client_version)
+        .build()?;
+    options.tls_cfg = tls_config_from_env(url)?;
+    options.retry_config = retry_config_from_env()?;
+
+    Ok(options)
+}
+
+/// How the worker's client identifies itself to Temporal.
+pub struct ClientIdentityConfig {
+    pub identity: String,
+    pub client_name: String,
+    pub client_version: String,
+}
+
+impl ClientIdentityConfig {
+    /// Reads `TEMPORAL_CLIENT_IDENTITY`, `TEMPORAL_CLIENT_NAME` and `TEMPORAL_CLIENT_VERSION`,
+    /// defaulting to `pid@hostname` and this crate's name and version.
+    pub fn from_env() -> Self {
+        let identity = env::var("TEMPORAL_CLIENT_IDENTITY").unwrap_or_else(|_| {
+            format!(
+                "{}@{}",
+                std::process::id(),
+                gethostname::gethostname().to_string_lossy()
+            )
+        });
+
+        Self {
+            identity,
+            client_name: env::var("TEMPORAL_CLIENT_NAME")
+                .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string()),
+            client_version: env::var("TEMPORAL_CLIENT_VERSION")
+                .unwrap_or_else(|_| env!("CARGO_PKG_VERSION").to_string()),
+        }
+    }
+}
+
+/// Errors if the client can't make calls in `namespace`: with an API key, every call carries
+/// the namespace the client connected to (see [api_key_headers]), so calls for another
+/// namespace need a client connected to it.
+pub fn check_api_key_namespace(client: &RetryClient<Client>, namespace: &str) -> Result<()> {
+    if client.namespace() != namespace && secrets::get("TEMPORAL_API_KEY")?.is_some() {
+        bail!(
+            "Client is connected to namespace {} with an API key, which can't be used for \
+             namespace {}; connect a separate client to it",
+            client.namespace(),
+            namespace
+        );
+    }
+
+    Ok(())
+}
+
+/// gRPC metadata sent on every call when the `TEMPORAL_API_KEY` secret is set, for API key
+/// authentication against Temporal Cloud namespaces. The `temporal-namespace` header is
+/// fixed to the namespace connected to, so the client only works for that namespace.
+fn api_key_headers(namespace: &str) -> Result<Option<Arc<RwLock<HashMap<String, String>>>>> {
+    let api_key = match secrets::get("TEMPORAL_API_KEY")? {
+        Some(api_key) => api_key,
+        None => return Ok(None),
+    };
+
+    let headers = HashMap::from([
+        ("authorization".to_string(), format!("Bearer {}", api_key)),
+        ("temporal-namespace".to_string(), namespace.to_string()),
+    ]);
+
+    Ok(Some(Arc::new(RwLock::new(headers))))
+}
+
+/// Reads TLS material from the environment:
+/// - `TEMPORAL_TLS_CA_CERT`: path to a PEM CA bundle for verifying the server
+/// - `TEMPORAL_TLS_CLIENT_CERT` / `TEMPORAL_TLS_CLIENT_KEY`: paths to the PEM client cert and key (mTLS)
+/// - `TEMPORAL_TLS_SERVER_NAME`: overrides the domain used to verify the server certificate
+///
+/// When a path variable isn't set, the PEM itself is looked up as a secret of the same name.
+fn tls_config_from_env(url: &Url) -> Result<Option<TlsConfig>> {
+    let client_tls_config = match (
+        read_tls_material("TEMPORAL_TLS_CLIENT_CERT")?,
+        read_tls_material("TEMPORAL_TLS_CLIENT_KEY")?,
+    ) {
+        (Some(client_cert), Some(client_private_key)) => Some(ClientTlsConfig {
+            client_cert,
+            client_private_key,
+        }),
+        (None, None) => None,
+        _ => bail!("TEMPORAL_TLS_CLIENT_CERT and TEMPORAL_TLS_CLIENT_KEY must be set together"),
+    };
+
+    tls_config(
+        url,
+        TlsConfig {
+            server_root_ca_cert: read_tls_material("TEMPORAL_TLS_CA_CERT")?,
+            domain: env::var("TEMPORAL_TLS_SERVER_NAME").ok(),
+            client_tls_config,
+        },
+    )
+}
+
+/// `tls` for an `https` url. An `http` url must come without TLS settings, so the scheme
+/// always says which transport is used.
+fn tls_config(url: &Url, tls: TlsConfig) -> Result<Option<TlsConfig>> {
+    if url.scheme() == "https" {
+        return Ok(Some(tls));
+    }
+
+    if tls.server_root_ca_cert.is_some() || tls.domain.is_some() || tls.client_tls_config.is_some()
+    {
+        bail!(
+            "TEMPORAL_TLS_* settings are set but {} doesn't use https; use an https url to \
+             connect with TLS",
+            url
+        );
+    }
+
+    Ok(None)
+}
+
+/// Backoff for retrying failed gRPC calls, starting from the SDK defaults:
+/// - `TEMPORAL_RETRY_MAX_RETRIES`
+/// - `TEMPORAL_RETRY_INITIAL_INTERVAL_MS` / `TEMPORAL_RETRY_MAX_INTERVAL_MS`
+/// - `TEMPORAL_RETRY_MAX_ELAPSED_TIME_MS`
+/// - `TEMPORAL_RETRY_MULTIPLIER`
+///
+/// Which status codes are retried is decided by the SDK and is not configurable.
+fn retry_config_from_env() -> Result<RetryConfig> {
+    let mut retry_config = RetryConfig::default();
+
+    retry_config.max_retries =
+        get_env_or_default("TEMPORAL_RETRY_MAX_RETRIES", retry_config.max_retries)?;
+    if let Some(initial_interval) = get_env_parsed("TEMPORAL_RETRY_INITIAL_INTERVAL_MS")? {
+        retry_config.initial_interval = Duration::from_millis(initial_interval);
+    }
+    if let Some(max_interval) = get_env_parsed("TEMPORAL_RETRY_MAX_INTERVAL_MS")? {
+        retry_config.max_interval = Duration::from_millis(max_interval);
+    }
+    if let Some(max_elapsed_time) = get_env_parsed("TEMPORAL_RETRY_MAX_ELAPSED_TIME_MS")? {
+        retry_config.max_elapsed_time = Some(Duration::from_millis(max_elapsed_time));
+    }
+    retry_config.multiplier =
+        get_env_or_default("TEMPORAL_RETRY_MULTIPLIER", retry_config.multiplier)?;
+
+    Ok(retry_config)
+}
+
+fn read_tls_material(var_name: &str) -> Result<Option<Vec<u8>>> {
+    match env::var(var_name) {
+        Ok(path) => fs::read(&path)
+            .map(Some)
+            .with_context(|| format!("Failed to read {} from {}", var_name, path)),
+        Err(_) => Ok(secrets::get(var_name)?.map(String::into_bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn no_tls() -> TlsConfig {
+        TlsConfig {
+            server_root_ca_cert: None,
+            domain: None,
+            client_tls_config: None,
+        }
+    }
+
+    #[test]
+    fn https_urls_use_tls() {
+        let url = Url::from_str("https://temporal.example.com:7233").unwrap();
+        assert!(tls_config(&url, no_tls()).unwrap().is_some());
+    }
+
+    #[test]
+    fn http_urls_without_tls_settings_use_plaintext() {
+        let url = Url::from_str("http://localhost:7233").unwrap();
+        assert!(tls_config(&url, no_tls()).unwrap().is_none());
+    }
+
+    #[test]
+    fn tls_settings_with_http_urls_are_rejected() {
+        let url = Url::from_str("http://localhost:7233").unwrap();
+        let tls = TlsConfig {
+            domain: Some("temporal.example.com".to_string()),
+            ..no_tls()
+        };
+
+        let err = tls_config(&url, tls).unwrap_err();
+        assert!(err.to_string().contains("https"), "{}", err);
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+static CLIENT_INTERCEPTORS: OnceCell<Vec<Box<dyn ClientInterceptor>>> = OnceCell::new();
+
+/// A workflow call made through the helpers in [crate::client].
+#[derive(Debug, Clone, Copy)]
+pub struct ClientCall<'a> {
+    /// `start`, `signal`, `signal_with_start`, `cancel`, `terminate` or `reset`.
+    pub operation: &'static str,
+    pub namespace: &'a str,
+    pub workflow_id: &'a str,
+}
+
+/// Hooks run around every start, signal, cancel, terminate and reset made through
+/// [crate::client], for logging and metrics that shouldn't be repeated at each call site.
+/// All hooks default to doing nothing.
+///
+/// Hooks can't change the request: each call has its own request type, so there is no
+/// common request to hand them.
+pub trait ClientInterceptor: Send + Sync {
+    /// Runs before the request is sent.
+    fn before(&self, _call: &ClientCall) {}
+
+    /// Runs after the call succeeds.
+    fn after(&self, _call: &ClientCall, _elapsed: Duration) {}
+
+    /// Runs after the call fails.
+    fn on_error(&self, _call: &ClientCall, _error: &anyhow::Error, _elapsed: Duration) {}
+}
+
+/// Sets the interceptors run around every client call, in order for `before` and in reverse
+/// for `after`/`on_error`. Call once at startup, before any client calls; later calls are
+/// ignored.
+pub fn init(interceptors: Vec<Box<dyn ClientInterceptor>>) {
+    let _ = CLIENT_INTERCEPTORS.set(interceptors);
+}
+
+fn interceptors() -> &'static [Box<dyn ClientInterceptor>] {
+    CLIENT_INTERCEPTORS.get_or_init(Vec::new)
+}
+
+/// Runs `request` with the interceptors set with [init] around it.
+pub async fn intercept<T>(
+    call: ClientCall<'_>,
+    request: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    for i in interceptors() {
+        i.before(&call);
+    }
+
+    let started = Instant::now();
+    let result = request.await;
+    let elapsed = started.elapsed();
+
+    for i in interceptors().iter().rev() {
+        match &result {
+            Ok(_) => i.after(&call, elapsed),
+            Err(e) => i.on_error(&call, e, elapsed),
+        }
+    }
+
+    result
+}
+
+/// Logs each client call, how long it took and whether it failed.
+pub struct LogCalls;
+
+impl ClientInterceptor for LogCalls {
+    fn after(&self, call: &ClientCall, elapsed: Duration) {
+        tracing::info!(
+            operation = call.operation,
+            namespace = call.namespace,
+            workflow_id = call.workflow_id,
+            ?elapsed,
+            "client call completed"
+        );
+    }
+
+    fn on_error(&self, call: &ClientCall, error: &anyhow::Error, elapsed: Duration) {
+        tracing::warn!(
+            operation = call.operation,
+            namespace = call.namespace,
+            workflow_id = call.workflow_id,
+            ?elapsed,
+            error = %format!("{:#}", error),
+            "client call failed"
+        );
+    }
+}
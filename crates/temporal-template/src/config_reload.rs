@@ -0,0 +1,63 @@
+use crate::{app_env::AppEnv, settings::WorkerSettings};
+use std::{
+    fs,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::watch;
+
+/// Publishes `initial`, then checks the [WorkerSettings::config_path] file every `interval`
+/// and republishes the reloaded settings whenever the file changes. A file that fails to
+/// load is logged and skipped, keeping the last good settings.
+///
+/// Only the `backlog` settings are read through the receiver (see
+/// [crate::backlog::watch_backlog]), so they are the only ones that change at runtime.
+/// Everything else (connection, worker limits and rate limits, telemetry) is read once at
+/// startup; a reload that changes any of it logs which fields were ignored until a restart.
+///
+/// Env vars still override the file, but `.env` files are only loaded at startup.
+pub fn watch_settings(
+    initial: WorkerSettings,
+    app_env: AppEnv,
+    interval: Duration,
+) -> watch::Receiver<WorkerSettings> {
+    let (sender, receiver) = watch::channel(initial.clone());
+
+    tokio::spawn(async move {
+        let mut last_modified = config_modified(app_env);
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let modified = config_modified(app_env);
+            if modified.is_none() || modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            match WorkerSettings::load_config(app_env) {
+                Ok(settings) => {
+                    tracing::info!("reloaded worker config");
+                    let ignored = settings.changes_needing_restart(&initial);
+                    if !ignored.is_empty() {
+                        tracing::warn!(
+                            ?ignored,
+                            "worker config changes that only take effect after a restart"
+                        );
+                    }
+                    if sender.send(settings).is_err() {
+                        // every receiver is gone, nothing left to publish to
+                        return;
+                    }
+                }
+                Err(e) => tracing::warn!(error = ?e, "failed to reload worker config"),
+            }
+        }
+    });
+
+    receiver
+}
+
+fn config_modified(app_env: AppEnv) -> Option<SystemTime> {
+    let path = WorkerSettings::config_path(app_env).ok()??;
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,67 @@
+use anyhow::{bail, Context, Result};
+use futures::future::BoxFuture;
+use std::str::FromStr;
+use temporal_sdk_core::Url;
+use trust_dns_resolver::TokioAsyncResolver;
+
+/// Finds the Temporal frontends to connect to, in the order they should be tried.
+pub trait ServiceDiscovery: Send + Sync {
+    fn discover(&self) -> BoxFuture<'_, Result<Vec<Url>>>;
+}
+
+/// Urls given up front, e.g. from `TEMPORAL_URLS`.
+pub struct StaticDiscovery {
+    pub urls: Vec<Url>,
+}
+
+impl ServiceDiscovery for StaticDiscovery {
+    fn discover(&self) -> BoxFuture<'_, Result<Vec<Url>>> {
+        Box::pin(async move { Ok(self.urls.clone()) })
+    }
+}
+
+/// Urls resolved from a DNS SRV record, e.g. a Kubernetes headless service's
+/// `_grpc._tcp.temporal-frontend.temporal.svc.cluster.local`. Targets are ordered by
+/// priority, then by weight (highest first).
+pub struct DnsSrvDiscovery {
+    pub record: String,
+    /// `http` or `https`.
+    pub scheme: String,
+}
+
+impl ServiceDiscovery for DnsSrvDiscovery {
+    fn discover(&self) -> BoxFuture<'_, Result<Vec<Url>>> {
+        Box::pin(async move {
+            let resolver = TokioAsyncResolver::tokio_from_system_conf()
+                .context("Failed to read the system DNS config")?;
+            let lookup = resolver
+                .srv_lookup(self.record.as_str())
+                .await
+                .with_context(|| format!("Failed to look up SRV record {}", self.record))?;
+
+            let mut records: Vec<_> = lookup.iter().collect();
+            records.sort_by_key(|srv| (srv.priority(), std::cmp::Reverse(srv.weight())));
+
+            let urls = records
+                .into_iter()
+                .map(|srv| {
+                    let host = srv.target().to_utf8();
+                    let url = format!(
+                        "{}://{}:{}",
+                        self.scheme,
+                        host.trim_end_matches('.'),
+                        srv.port()
+                    );
+                    Url::from_str(&url).with_context(|| format!("Invalid discovered url {}", url))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if urls.is_empty() {
+                bail!("SRV record {} has no targets", self.record);
+            }
+
+            tracing::info!(record = %self.record, ?urls, "discovered temporal frontends");
+            Ok(urls)
+        })
+    }
+}
@@ -0,0 +1,379 @@
+//! A workflow that runs a declarative definition passed as its argument, so simple
+//! automations can be added without writing a new workflow function. See
+//! `dsl.example.yaml` for the format.
+//!
+//! Inputs are templated from earlier results: a string that is exactly `${name.field}` is
+//! replaced by that value, and `${...}` inside a longer string is replaced by its text.
+
+use crate::{registry, wf_activities, workflow_interceptor};
+use anyhow::{anyhow, Context, Result};
+use futures::{future::BoxFuture, StreamExt};
+use serde::{de, Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::time::Duration;
+use temporal_sdk::{ActivityOptions, WfContext, WfExitValue};
+use temporal_sdk_core_protos::temporal::api::common::v1::RetryPolicy;
+
+type Variables = Map<String, Value>;
+
+/// The workflow argument: a definition, or the text of one in YAML (or JSON).
+pub struct DslInput(DslWorkflow);
+
+impl<'de> Deserialize<'de> for DslInput {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // parse as a definition unless given text, so mistakes in a definition are reported
+        // as such instead of as matching neither form
+        let definition = match Value::deserialize(deserializer)? {
+            Value::String(text) => serde_yaml::from_str(&text).map_err(de::Error::custom)?,
+            value => DslWorkflow::deserialize(value).map_err(de::Error::custom)?,
+        };
+        Ok(Self(definition))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DslWorkflow {
+    /// Initial values available to templates.
+    #[serde(default)]
+    pub variables: Variables,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    /// Runs `name` with the templated `input`, storing its json result as `result`.
+    Activity {
+        name: String,
+        #[serde(default)]
+        input: Value,
+        result: Option<String>,
+        start_to_close_timeout_secs: Option<u64>,
+        retry: Option<DslRetry>,
+    },
+    /// Runs `then` if the variable at `when` equals `equals`, otherwise `otherwise`.
+    Branch {
+        when: String,
+        equals: Value,
+        then: Vec<Step>,
+        #[serde(default)]
+        otherwise: Vec<Step>,
+    },
+    /// Waits for the signal `name`, storing its json input as `result`.
+    WaitSignal {
+        name: String,
+        result: Option<String>,
+    },
+    /// Sleeps on a workflow timer.
+    Sleep { seconds: u64 },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DslRetry {
+    /// Required, so retrying forever has to be asked for: 0 means unlimited attempts.
+    pub max_attempts: i32,
+    pub initial_interval_secs: Option<u64>,
+    pub max_interval_secs: Option<u64>,
+}
+
+registry::workflow!("dsl_workflow", dsl_workflow_fn);
+
+async fn dsl_workflow_fn(ctx: WfContext, input: DslInput) -> Result<WfExitValue<()>> {
+    let DslInput(mut definition) = input;
+
+    let steps = std::mem::take(&mut definition.steps);
+    run_steps(&ctx, &steps, &mut definition.variables).await?;
+
+    Ok(().into())
+}
+
+fn run_steps<'a>(
+    ctx: &'a WfContext,
+    steps: &'a [Step],
+    vars: &'a mut Variables,
+) -> BoxFuture<'a, Result<()>> {
+    Box::pin(async move {
+        for step in steps {
+            run_step(ctx, step, vars).await?;
+        }
+        Ok(())
+    })
+}
+
+async fn run_step(ctx: &WfContext, step: &Step, vars: &mut Variables) -> Result<()> {
+    match step {
+        Step::Activity {
+            name,
+            input,
+            result,
+            start_to_close_timeout_secs,
+            retry,
+        } => {
+            let input = render(input, vars)?;
+            let defaults = wf_activities::activity_options(name, &input)?;
+
+            let options = ActivityOptions {
+                start_to_close_timeout: start_to_close_timeout_secs
+                    .map(Duration::from_secs)
+                    .or(defaults.start_to_close_timeout),
+                retry_policy: match retry {
+                    Some(retry) => Some(retry.to_policy()?),
+                    None => defaults.retry_policy.clone(),
+                },
+                ..defaults
+            };
+
+            let output: Value =
+                wf_activities::activity_result(workflow_interceptor::activity(ctx, options).await)
+                    .with_context(|| format!("Activity step {} failed", name))?;
+
+            if let Some(var) = result {
+                vars.insert(var.clone(), output);
+            }
+        }
+        Step::Branch {
+            when,
+            equals,
+            then,
+            otherwise,
+        } => {
+            let branch = if lookup(when, vars)? == *equals {
+                then
+            } else {
+                otherwise
+            };
+            run_steps(ctx, branch, vars).await?;
+        }
+        Step::WaitSignal { name, result } => {
+            let signal = Box::pin(workflow_interceptor::signal_channel(ctx, name))
+                .next()
+                .await
+                .ok_or_else(|| anyhow!("Signal channel {} closed", name))?;
+
+            if let Some(var) = result {
+                let value = match signal.input.first() {
+                    Some(payload) => serde_json::from_slice(&payload.data)
+                        .with_context(|| format!("Signal {} input is not json", name))?,
+                    None => Value::Null,
+                };
+                vars.insert(var.clone(), value);
+            }
+        }
+        Step::Sleep { seconds } => {
+            ctx.timer(Duration::from_secs(*seconds)).await;
+        }
+    }
+
+    Ok(())
+}
+
+impl DslRetry {
+    fn to_policy(&self) -> Result<RetryPolicy> {
+        Ok(RetryPolicy {
+            maximum_attempts: self.max_attempts,
+            initial_interval: self
+                .initial_interval_secs
+                .map(|secs| Duration::from_secs(secs).try_into())
+                .transpose()?,
+            maximum_interval: self
+                .max_interval_secs
+                .map(|secs| Duration::from_secs(secs).try_into())
+                .transpose()?,
+            backoff_coefficient: 2.0,
+            ..Default::default()
+        })
+    }
+}
+
+/// Replaces `${...}` references in `template` with values from `vars`.
+fn render(template: &Value, vars: &Variables) -> Result<Value> {
+    Ok(match template {
+        Value::String(s) => render_str(s, vars)?,
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, vars))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), render(value, vars)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+fn render_str(s: &str, vars: &Variables) -> Result<Value> {
+    // a lone reference keeps the value's type
+    if let Some(path) = s.strip_prefix("${").and_then(|rest| rest.strip_suffix('}')) {
+        if !path.contains('}') {
+            return lookup(path, vars);
+        }
+    }
+
+    let mut rendered = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| anyhow!("Unclosed reference in {:?}", s))?;
+
+        rendered.push_str(&rest[..start]);
+        match lookup(&rest[start + 2..end], vars)? {
+            Value::String(value) => rendered.push_str(&value),
+            value => rendered.push_str(&value.to_string()),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    Ok(Value::String(rendered))
+}
+
+/// Looks up a dotted path such as `ticket.assignees.0.name`.
+fn lookup(path: &str, vars: &Variables) -> Result<Value> {
+    let mut parts = path.trim().split('.');
+    let name = parts.next().unwrap_or_default();
+    let mut value = vars
+        .get(name)
+        .ok_or_else(|| anyhow!("Unknown variable {}", name))?;
+
+    for part in parts {
+        value = match value {
+            Value::Object(fields) => fields.get(part),
+            Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow!("{} has no {}", path, part))?;
+    }
+
+    Ok(value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars() -> Variables {
+        match json!({
+            "count": 3,
+            "user": { "name": "ada" },
+            "tickets": [{ "id": "SEC-1" }, { "id": "SEC-2" }]
+        }) {
+            Value::Object(vars) => vars,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn lone_reference_keeps_type() {
+        assert_eq!(render_str("${count}", &vars()).unwrap(), json!(3));
+        assert_eq!(
+            render_str("${user}", &vars()).unwrap(),
+            json!({ "name": "ada" })
+        );
+    }
+
+    #[test]
+    fn mixed_references_are_interpolated() {
+        assert_eq!(
+            render_str(
+                "${user.name} has ${count} open, first ${tickets.0.id}",
+                &vars()
+            )
+            .unwrap(),
+            json!("ada has 3 open, first SEC-1")
+        );
+        assert_eq!(
+            render_str("${count}-${user.name}", &vars()).unwrap(),
+            json!("3-ada")
+        );
+        assert_eq!(
+            render_str("${count} of {total}", &vars()).unwrap(),
+            json!("3 of {total}")
+        );
+    }
+
+    #[test]
+    fn renders_nested_values() {
+        let template = json!({ "ids": ["${tickets.1.id}", 7], "who": "${user.name}" });
+        assert_eq!(
+            render(&template, &vars()).unwrap(),
+            json!({ "ids": ["SEC-2", 7], "who": "ada" })
+        );
+    }
+
+    #[test]
+    fn unclosed_reference_is_an_error() {
+        assert!(render_str("hello ${user.name", &vars()).is_err());
+    }
+
+    #[test]
+    fn missing_paths_are_errors() {
+        assert!(lookup("nobody", &vars()).is_err());
+        assert!(lookup("user.email", &vars()).is_err());
+        assert!(lookup("tickets.5.id", &vars()).is_err());
+        assert!(lookup("tickets.first", &vars()).is_err());
+    }
+
+    #[test]
+    fn parses_example_definition() {
+        let definition: DslWorkflow =
+            serde_yaml::from_str(include_str!("../dsl.example.yaml")).unwrap();
+        assert_eq!(definition.steps.len(), 3);
+    }
+
+    #[test]
+    fn input_accepts_definition_or_text() {
+        let DslInput(definition) =
+            serde_json::from_value(json!({ "steps": [{ "sleep": { "seconds": 1 } }] })).unwrap();
+        assert_eq!(definition.steps.len(), 1);
+
+        let DslInput(definition) =
+            serde_json::from_value(json!("steps:\n  - sleep:\n      seconds: 1\n")).unwrap();
+        assert_eq!(definition.steps.len(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        for definition in [
+            json!({ "steps": [], "step": [] }),
+            json!({ "steps": [{ "sleep": { "seconds": 1, "minutes": 1 } }] }),
+            json!({ "steps": [{ "activity": { "name": "echo_activity", "timeout": 5 } }] }),
+            json!({ "steps": [{ "activity": {
+                "name": "echo_activity",
+                "retry": { "max_attempts": 3, "backoff": 2.0 }
+            } }] }),
+        ] {
+            assert!(
+                serde_json::from_value::<DslWorkflow>(definition.clone()).is_err(),
+                "{}",
+                definition
+            );
+        }
+    }
+
+    #[test]
+    fn retry_needs_max_attempts() {
+        let err = serde_json::from_value::<DslWorkflow>(json!({ "steps": [{ "activity": {
+            "name": "echo_activity",
+            "retry": { "initial_interval_secs": 1 }
+        } }] }))
+        .unwrap_err();
+        assert!(err.to_string().contains("max_attempts"), "{}", err);
+    }
+
+    #[test]
+    fn input_reports_definition_errors() {
+        let err = serde_json::from_value::<DslInput>(json!({ "steps": [{ "slep": {} }] }))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("slep"), "{}", err);
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{anyhow, Context, Result};
+use std::{any::type_name, env, fmt::Display, str::FromStr};
+use url::Url;
+
+/// Parses `var_name` into `T` if it is set. Errors name the variable, its value and the
+/// type it was parsed as.
+pub fn get_env_parsed<T>(var_name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match env::var(var_name) {
+        Ok(value) => parse_value(var_name, &value).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_value<T>(var_name: &str, value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|e| {
+        anyhow!(
+            "Invalid {} {:?}, expected {}: {}",
+            var_name,
+            value,
+            type_name::<T>(),
+            e
+        )
+    })
+}
+
+/// Parses `var_name` into `T`, or returns `default` if it isn't set.
+pub fn get_env_or_default<T>(var_name: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    Ok(get_env_parsed(var_name)?.unwrap_or(default))
+}
+
+/// Where the service playing `role` (e.g. `temporal`) is reachable:
+/// - `<ROLE>_SERVICE_URL`, a full url with any scheme. `grpc://` and `grpcs://` are read as
+///   `http://` and `https://`
+/// - otherwise `http://<ROLE>_HOST:<ROLE>_PORT`, with the port defaulting to `default_port`
+///
+/// `None` when neither url nor host is set.
+pub fn get_service_url(role: &str, default_port: u16) -> Result<Option<Url>> {
+    let role = role.to_uppercase();
+
+    service_url(
+        &role,
+        env::var(format!("{}_SERVICE_URL", role)).ok(),
+        env::var(format!("{}_HOST", role)).ok(),
+        get_env_parsed(&format!("{}_PORT", role))?,
+        default_port,
+    )
+}
+
+fn service_url(
+    role: &str,
+    url: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    default_port: u16,
+) -> Result<Option<Url>> {
+    let url = match (url, host) {
+        (Some(url), _) => match url.split_once("://") {
+            Some(("grpc", rest)) => format!("http://{}", rest),
+            Some(("grpcs", rest)) => format!("https://{}", rest),
+            _ => url,
+        },
+        (None, Some(host)) => format!("http://{}:{}", host, port.unwrap_or(default_port)),
+        (None, None) => return Ok(None),
+    };
+
+    Url::from_str(&url)
+        .map(Some)
+        .with_context(|| format!("Invalid {} service url: {}", role, url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(service_url: Option<&str>, host: Option<&str>, port: Option<u16>) -> Option<String> {
+        super::service_url(
+            "TEMPORAL",
+            service_url.map(String::from),
+            host.map(String::from),
+            port,
+            7233,
+        )
+        .unwrap()
+        .map(|url| url.to_string())
+    }
+
+    #[test]
+    fn service_url_rewrites_grpc_schemes() {
+        assert_eq!(
+            url(Some("grpc://temporal:7233"), None, None).as_deref(),
+            Some("http://temporal:7233/")
+        );
+        assert_eq!(
+            url(Some("grpcs://ns.tmprl.cloud:7233"), None, None).as_deref(),
+            Some("https://ns.tmprl.cloud:7233/")
+        );
+        assert_eq!(
+            url(Some("https://temporal:443"), Some("ignored"), None).as_deref(),
+            Some("https://temporal/")
+        );
+    }
+
+    #[test]
+    fn service_url_falls_back_to_host_and_port() {
+        assert_eq!(
+            url(None, Some("temporal"), Some(7234)).as_deref(),
+            Some("http://temporal:7234/")
+        );
+        assert_eq!(
+            url(None, Some("temporal"), None).as_deref(),
+            Some("http://temporal:7233/")
+        );
+        assert_eq!(url(None, None, Some(7234)), None);
+    }
+
+    #[test]
+    fn invalid_service_url_names_role() {
+        let err = service_url("TEMPORAL", Some("not a url".into()), None, None, 7233).unwrap_err();
+        assert!(err.to_string().contains("TEMPORAL"), "{}", err);
+    }
+
+    #[test]
+    fn parse_error_names_variable_value_and_type() {
+        let err = parse_value::<u16>("TEMPORAL_PORT", "seventy").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid TEMPORAL_PORT \"seventy\", expected u16: invalid digit found in string"
+        );
+    }
+}
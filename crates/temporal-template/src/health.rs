@@ -0,0 +1,102 @@
+use crate::client::{check_temporal_health, TemporalHealth};
+use anyhow::Result;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Response, Server, StatusCode,
+};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use temporal_sdk_core::{Client, RetryClient};
+
+/// What the worker is doing, as reported to Kubernetes probes by [serve].
+#[derive(Debug, Default)]
+pub struct HealthState {
+    connected: AtomicBool,
+    polling: AtomicBool,
+    shutting_down: AtomicBool,
+}
+
+impl HealthState {
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub fn set_polling(&self) {
+        self.polling.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    /// Connected to Temporal, polling for tasks, and not shutting down.
+    pub fn is_ready(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+            && self.polling.load(Ordering::Relaxed)
+            && !self.shutting_down.load(Ordering::Relaxed)
+    }
+
+    fn respond(&self, path: &str) -> Response<Body> {
+        let (status, body) = match path {
+            // the process is up and serving requests
+            "/healthz" => (StatusCode::OK, "ok".to_string()),
+            "/readyz" if self.is_ready() => (StatusCode::OK, "ready".to_string()),
+            "/readyz" => (StatusCode::SERVICE_UNAVAILABLE, format!("{:?}", self)),
+            _ => (StatusCode::NOT_FOUND, "not found".to_string()),
+        };
+
+        let mut response = Response::new(Body::from(body));
+        *response.status_mut() = status;
+        response
+    }
+}
+
+/// Checks Temporal with [check_temporal_health] every `interval`, so readiness follows the
+/// connection instead of only the check made at startup. Runs forever; spawn it.
+pub async fn watch_connection(
+    client: RetryClient<Client>,
+    health: Arc<HealthState>,
+    interval: Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        match check_temporal_health(&client, Duration::from_secs(10)).await {
+            TemporalHealth::Serving => health.set_connected(true),
+            TemporalHealth::Unavailable(message) => {
+                tracing::warn!(%message, "temporal is unavailable");
+                health.set_connected(false);
+            }
+            TemporalHealth::TimedOut => {
+                tracing::warn!("temporal health check timed out");
+                health.set_connected(false);
+            }
+        }
+    }
+}
+
+/// Serves `/healthz` (liveness) and `/readyz` (readiness) on `address` until the process
+/// exits.
+pub async fn serve(address: SocketAddr, health: Arc<HealthState>) -> Result<()> {
+    let make_service = make_service_fn(move |_conn| {
+        let health = health.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let response = health.respond(req.uri().path());
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+
+    tracing::info!(%address, "serving health checks");
+    Server::try_bind(&address)?.serve(make_service).await?;
+
+    Ok(())
+}
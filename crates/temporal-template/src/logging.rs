@@ -0,0 +1,62 @@
+use serde_json::json;
+use std::{
+    io::{self, Write},
+    time::{Duration, UNIX_EPOCH},
+};
+use temporal_sdk_core::fetch_global_buffered_logs;
+use tokio::task::JoinHandle;
+
+/// Prints the logs core buffers in [temporal_sdk_core_api::telemetry::Logger::Forward] mode
+/// (the worker's own `tracing` events included, since core owns the global subscriber) as
+/// one json object per line on stdout. Used for `log_format = "json"`, as core's console
+/// logger only prints text.
+///
+/// Logs are printed every `interval` while this is alive, and whatever is still buffered
+/// when it's dropped, so keep it alive until the process exits.
+pub struct JsonLogPrinter {
+    task: JoinHandle<()>,
+}
+
+impl JsonLogPrinter {
+    pub fn start(interval: Duration) -> Self {
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                print_buffered_logs();
+            }
+        });
+
+        Self { task }
+    }
+}
+
+impl Drop for JsonLogPrinter {
+    fn drop(&mut self) {
+        self.task.abort();
+        print_buffered_logs();
+    }
+}
+
+fn print_buffered_logs() {
+    let logs = fetch_global_buffered_logs();
+    if logs.is_empty() {
+        return;
+    }
+
+    let mut stdout = io::stdout().lock();
+    for log in logs {
+        let line = json!({
+            "timestamp_ms": log
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            "level": log.level.to_string(),
+            "target": log.target,
+            "message": log.message,
+            "fields": log.fields,
+        });
+        // nowhere left to report a failed write to
+        let _ = writeln!(stdout, "{}", line);
+    }
+}
@@ -1,64 +1,573 @@
-use anyhow::Result;
+mod activity_ext;
+mod activity_middleware;
+mod app_env;
+mod async_completion;
+mod backlog;
+mod batch;
+mod cli;
+mod client;
+mod client_interceptor;
+mod config_reload;
+mod discovery;
+mod dsl;
+mod env_vars;
+mod health;
+mod logging;
+mod rate_limit;
+mod registry;
+mod replay;
+mod resources;
+mod saga;
+mod schedules;
+mod secrets;
+mod settings;
+#[cfg(test)]
+mod test_env;
+mod wf_activities;
+mod wf_utils;
+mod worker_ext;
+mod worker_set;
+mod workflow_interceptor;
+
+use activity_ext::ActContextExt;
+use anyhow::{bail, Result};
+use cli::Command;
+use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
 use std::{
-    str::FromStr,
+    future::Future,
+    path::Path,
     sync::Arc,
-    time::{Duration, Instant},
-};
-use temporal_sdk::{
-    sdk_client_options, ActContext, ActivityOptions, WfContext, WfExitValue, Worker,
-};
-use temporal_sdk_core::{
-    init_worker, protos::coresdk::AsJsonPayloadExt, telemetry_init, TelemetryOptionsBuilder, Url,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use temporal_sdk_core_api::worker::WorkerConfigBuilder;
-use temporal_sdk_core_protos::coresdk::activity_result::activity_resolution::Status;
+use temporal_sdk::{ActContext, ActExitValue, ActivityOptions, WfContext, WfExitValue};
+use temporal_sdk_core::{telemetry_init, Client, RetryClient};
+use worker_set::{WorkerDefinition, WorkerSet};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("starting test worker server");
+    let app_env = app_env::AppEnv::from_env()?;
+    let settings = settings::WorkerSettings::load(app_env)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = Command::from_args(&args)?;
 
-    let server_options = sdk_client_options(Url::from_str("http://localhost:7233")?).build()?;
+    let mut telemetry_options = settings.telemetry.telemetry_options()?;
+    if command != Command::RunWorker {
+        // one-off commands only log; serving metrics or exporting traces from them would
+        // clash with a worker running on the same host
+        telemetry_options.metrics = None;
+        telemetry_options.tracing = None;
+    }
+    // core owns the global tracing subscriber, so this comes before anything is logged
+    telemetry_init(&telemetry_options)?;
+    // held until main returns, so logs buffered at exit are printed too
+    let _json_logs = (settings.telemetry.log_format == settings::LogFormat::Json)
+        .then(|| logging::JsonLogPrinter::start(Duration::from_millis(100)));
+    client_interceptor::init(vec![Box::new(client_interceptor::LogCalls)]);
 
-    let client = server_options.connect("default", None, None).await?;
+    match command {
+        Command::RunWorker => {}
+        Command::Replay { paths } => {
+            replay::replay_files(&settings, &paths).await?;
+            return Ok(());
+        }
+        Command::RegisterNamespace {
+            namespace,
+            retention,
+            description,
+        } => {
+            let client = connect(&settings).await?;
+            client::register_namespace(&client, &namespace, retention, &description).await?;
+            println!("registered namespace {}", namespace);
+            return Ok(());
+        }
+        Command::DescribeNamespace { namespace } => {
+            let client = connect(&settings).await?;
+            let namespace = namespace.unwrap_or_else(|| settings.namespace.clone());
+            let description = client::describe_namespace(&client, &namespace).await?;
+            println!("name:          {}", description.name);
+            println!("description:   {}", description.description);
+            match description.retention {
+                Some(retention) => {
+                    println!(
+                        "retention:     {} days",
+                        retention.as_secs() / (24 * 60 * 60)
+                    )
+                }
+                None => println!("retention:     -"),
+            }
+            return Ok(());
+        }
+        Command::UpdateNamespace {
+            namespace,
+            retention,
+            description,
+        } => {
+            let client = connect(&settings).await?;
+            client::update_namespace(&client, &namespace, retention, description.as_deref())
+                .await?;
+            println!("updated namespace {}", namespace);
+            return Ok(());
+        }
+        Command::ExportHistory {
+            workflow_id,
+            path,
+            run_id,
+        } => {
+            let client = connect(&settings).await?;
+            let history = client::get_workflow_history(
+                &client,
+                &settings.namespace,
+                &workflow_id,
+                run_id.as_deref(),
+                false,
+            )
+            .await?;
+            replay::save_history(Path::new(&path), &history)?;
+            println!(
+                "saved {} events of {} to {}",
+                history.events.len(),
+                workflow_id,
+                path
+            );
+            return Ok(());
+        }
+        Command::DescribeTaskQueue { task_queue } => {
+            let client = connect(&settings).await?;
+            let task_queue = task_queue.unwrap_or_else(|| settings.task_queue.clone());
+            let description =
+                client::describe_task_queue(&client, &settings.namespace, &task_queue).await?;
 
-    let telemetry_options = TelemetryOptionsBuilder::default().build()?;
-    telemetry_init(&telemetry_options)?;
+            println!("task queue:        {}", task_queue);
+            println!("workflow backlog:  {}", description.workflow_backlog);
+            println!("activity backlog:  {}", description.activity_backlog);
+            for (kind, pollers) in [
+                ("workflow", &description.workflow_pollers),
+                ("activity", &description.activity_pollers),
+            ] {
+                for poller in pollers {
+                    println!(
+                        "{} poller {}: last polled {}, {} tasks/s",
+                        kind,
+                        poller.identity,
+                        format_time(poller.last_access_time),
+                        poller.rate_per_second
+                    );
+                }
+            }
+            return Ok(());
+        }
+        Command::ExecuteWorkflow {
+            workflow_type,
+            input,
+            workflow_id,
+        } => {
+            let client = connect(&settings).await?;
+            let workflow_id = workflow_id
+                .unwrap_or_else(|| format!("{}-{}", workflow_type, uuid::Uuid::new_v4()));
 
-    let worker_config = WorkerConfigBuilder::default()
-        .namespace("security-engineering")
-        .task_queue("task_queue")
-        .worker_build_id("some_unique_thing")
-        .build()?;
+            let result: serde_json::Value = client::start_workflow_and_await_result(
+                &client,
+                &settings.namespace,
+                &settings.task_queue,
+                &workflow_id,
+                &workflow_type,
+                &input,
+            )
+            .await?;
+            println!("{}", result);
+            return Ok(());
+        }
+        Command::DescribeWorkflow {
+            workflow_id,
+            run_id,
+        } => {
+            let client = connect(&settings).await?;
+            let description = client::describe_workflow(
+                &client,
+                &settings.namespace,
+                &workflow_id,
+                run_id.as_deref(),
+            )
+            .await?;
 
-    let core_worker = init_worker(worker_config, client);
+            let summary = &description.summary;
+            println!("workflow id:   {}", summary.workflow_id);
+            println!("run id:        {}", summary.run_id);
+            println!("type:          {}", summary.workflow_type);
+            println!("status:        {:?}", summary.status);
+            println!("task queue:    {}", summary.task_queue);
+            println!("started:       {}", format_time(summary.start_time));
+            println!("closed:        {}", format_time(summary.close_time));
+            for activity in &description.pending_activities {
+                println!(
+                    "pending activity {} ({}): {:?}, attempt {}, last heartbeat {}, last failure {:?}",
+                    activity.activity_id,
+                    activity.activity_type,
+                    activity.state,
+                    activity.attempt,
+                    format_time(activity.last_heartbeat_time),
+                    activity.last_failure,
+                );
+            }
+            return Ok(());
+        }
+        Command::ListWorkflows { query } => {
+            let client = connect(&settings).await?;
+            let mut workflows = Box::pin(client::list_all_workflows(
+                &client,
+                &settings.namespace,
+                &query,
+                100,
+            ));
 
-    let mut worker = Worker::new_from_core(Arc::new(core_worker), "task_queue");
-    worker.register_activity(
-        "echo_activity",
-        |_ctx: ActContext, echo_me: String| async move { Ok(echo_me) },
-    );
+            while let Some(workflow) = workflows.try_next().await? {
+                println!(
+                    "{}\t{}\t{}\t{:?}\t{}",
+                    workflow.workflow_id,
+                    workflow.run_id,
+                    workflow.workflow_type,
+                    workflow.status,
+                    format_time(workflow.start_time)
+                );
+            }
+            return Ok(());
+        }
+        Command::SignalWorkflow {
+            workflow_id,
+            signal_name,
+            input,
+        } => {
+            let client = connect(&settings).await?;
+            client::signal_workflow(
+                &client,
+                &settings.namespace,
+                &workflow_id,
+                None,
+                &signal_name,
+                &input,
+            )
+            .await?;
+            println!("signalled {} to {}", signal_name, workflow_id);
+            return Ok(());
+        }
+        Command::SignalWithStart {
+            workflow_type,
+            workflow_id,
+            input,
+            signal_name,
+            signal_input,
+        } => {
+            let client = connect(&settings).await?;
+            let run_id = client::signal_with_start_workflow(
+                &client,
+                &settings.namespace,
+                &settings.task_queue,
+                &workflow_id,
+                &workflow_type,
+                &input,
+                &signal_name,
+                &signal_input,
+            )
+            .await?;
+            println!(
+                "signalled {} to {} run {}",
+                signal_name, workflow_id, run_id
+            );
+            return Ok(());
+        }
+        Command::CancelWorkflow {
+            workflow_id,
+            reason,
+        } => {
+            let client = connect(&settings).await?;
+            client::cancel_workflow(&client, &settings.namespace, &workflow_id, None, &reason)
+                .await?;
+            println!("requested cancellation of {}", workflow_id);
+            return Ok(());
+        }
+        Command::TerminateWorkflow {
+            workflow_id,
+            reason,
+            details,
+        } => {
+            let client = connect(&settings).await?;
+            client::terminate_workflow(
+                &client,
+                &settings.namespace,
+                &workflow_id,
+                None,
+                &reason,
+                details.as_ref(),
+            )
+            .await?;
+            println!("terminated {}", workflow_id);
+            return Ok(());
+        }
+        Command::ResetWorkflow {
+            workflow_id,
+            run_id,
+            event_id,
+            reason,
+        } => {
+            let client = connect(&settings).await?;
+            let new_run_id = client::reset_workflow(
+                &client,
+                &settings.namespace,
+                &workflow_id,
+                &run_id,
+                event_id,
+                &reason,
+            )
+            .await?;
+            println!("reset {} to new run {}", workflow_id, new_run_id);
+            return Ok(());
+        }
+        Command::CreateSchedule { schedule_id, path } => {
+            let definition = schedules::ScheduleDefinition::from_file(Path::new(&path))?;
+            let client = connect(&settings).await?;
+            schedules::create_schedule(&client, &settings.namespace, &schedule_id, &definition)
+                .await?;
+            println!("created schedule {}", schedule_id);
+            return Ok(());
+        }
+        Command::DescribeSchedule { schedule_id } => {
+            let client = connect(&settings).await?;
+            let description =
+                schedules::describe_schedule(&client, &settings.namespace, &schedule_id).await?;
+
+            println!("schedule:      {}", schedule_id);
+            println!("paused:        {}", description.paused);
+            println!("notes:         {}", description.notes);
+            println!("started:       {} workflows", description.action_count);
+            for workflow_id in &description.running_workflows {
+                println!("running:       {}", workflow_id);
+            }
+            for time in &description.next_action_times {
+                println!("next run:      {}", format_time(Some(*time)));
+            }
+            return Ok(());
+        }
+        Command::UpdateSchedule { schedule_id, path } => {
+            let definition = schedules::ScheduleDefinition::from_file(Path::new(&path))?;
+            let client = connect(&settings).await?;
+            schedules::update_schedule(&client, &settings.namespace, &schedule_id, &definition)
+                .await?;
+            println!("updated schedule {}", schedule_id);
+            return Ok(());
+        }
+        Command::DeleteSchedule { schedule_id } => {
+            let client = connect(&settings).await?;
+            schedules::delete_schedule(&client, &settings.namespace, &schedule_id).await?;
+            println!("deleted schedule {}", schedule_id);
+            return Ok(());
+        }
+        Command::Batch {
+            query,
+            operation,
+            dry_run,
+        } => {
+            let client = connect(&settings).await?;
+            let progress = batch::run_batch(
+                &client,
+                &settings.namespace,
+                &query,
+                &operation,
+                10,
+                dry_run,
+                |progress| {
+                    if !dry_run {
+                        eprint!(
+                            "\r{}/{} done, {} failed",
+                            progress.succeeded + progress.failed,
+                            progress.matched,
+                            progress.failed
+                        );
+                    }
+                },
+            )
+            .await?;
+
+            if dry_run {
+                println!("{} workflows match", progress.matched);
+            } else {
+                eprintln!();
+                println!(
+                    "{} workflows matched, {} succeeded, {} failed",
+                    progress.matched, progress.succeeded, progress.failed
+                );
+            }
+            return Ok(());
+        }
+        Command::CompleteActivity { token, result } => {
+            let client = connect(&settings).await?;
+            async_completion::complete_async(&client, &token, &result).await?;
+            println!("completed activity");
+            return Ok(());
+        }
+        Command::FailActivity { token, message } => {
+            let client = connect(&settings).await?;
+            async_completion::fail_async(&client, &token, &message).await?;
+            println!("failed activity");
+            return Ok(());
+        }
+    }
+
+    tracing::info!(?settings, "starting test worker server");
+
+    let health = Arc::new(health::HealthState::default());
+    if let Some(address) = settings.health_bind_address {
+        let state = health.clone();
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(address, state).await {
+                tracing::error!(error = ?e, "health server stopped");
+            }
+        });
+    }
 
-    worker.register_activity("test_activity_fn", test_activity_fn);
+    // backlog settings read from this receiver follow config file edits without a restart
+    let reloaded_settings =
+        config_reload::watch_settings(settings.clone(), app_env, Duration::from_secs(30));
 
-    // testing new stuff for workflow functions
-    worker.register_wf("test_workflow_fn", test_workflow_fn);
+    let client = connect(&settings).await?;
 
-    worker.run().await?;
+    match client::check_temporal_health(&client, Duration::from_secs(10)).await {
+        client::TemporalHealth::Serving => {
+            tracing::info!("temporal is serving");
+            health.set_connected(true);
+        }
+        client::TemporalHealth::Unavailable(message) => {
+            return Err(format!("temporal is unavailable: {}", message).into())
+        }
+        client::TemporalHealth::TimedOut => return Err("temporal health check timed out".into()),
+    }
+    tokio::spawn(health::watch_connection(
+        client.clone(),
+        health.clone(),
+        Duration::from_secs(15),
+    ));
+
+    wf_activities::ActivityDefaults::init(wf_activities::ActivityDefaults::from_env()?);
+    activity_middleware::init(vec![Box::new(activity_middleware::LogTiming)]);
+    workflow_interceptor::init(vec![Box::new(workflow_interceptor::LogWorkflows)]);
+
+    // replace the callback to drive an autoscaler instead of just logging
+    tokio::spawn(backlog::watch_backlog(
+        client.clone(),
+        settings.namespace.clone(),
+        settings.task_queue.clone(),
+        reloaded_settings,
+        |signal, backlog| {
+            tracing::info!(
+                ?signal,
+                total = backlog.total(),
+                "backlog threshold crossed"
+            )
+        },
+    ));
+
+    // add a definition per extra task queue this binary should serve
+    let workers = WorkerSet::new(
+        client,
+        &settings,
+        vec![WorkerDefinition {
+            namespace: settings.namespace.clone(),
+            task_queue: settings.task_queue.clone(),
+            register: registry::register_all,
+        }],
+    )?
+    .with_health(health);
+
+    workers.run().await?;
 
     Ok(())
 }
 
+/// Connects to the configured namespace, failing over between the discovered frontends.
+async fn connect(settings: &settings::WorkerSettings) -> Result<RetryClient<Client>> {
+    // add providers (e.g. a vault client) to the chain to read credentials from elsewhere
+    secrets::init(Box::new(secrets::SecretsChain::from_env()));
+
+    client::connect_to_namespace(
+        &settings.service_discovery()?.discover().await?,
+        &settings.namespace,
+        |failed_url, next_url, err| {
+            tracing::warn!(%failed_url, %next_url, ?err, "failed to connect, failing over")
+        },
+    )
+    .await
+}
+
+/// `time` as an RFC 3339 UTC timestamp, or `-` when unset.
+fn format_time(time: Option<SystemTime>) -> String {
+    let secs = match time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        Some(since_epoch) => since_epoch.as_secs(),
+        None => return "-".to_string(),
+    };
+
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let days = secs / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+async fn echo_activity(_ctx: ActContext, echo_me: String) -> Result<String> {
+    Ok(echo_me)
+}
+
+registry::activity!("echo_activity", echo_activity);
+
+registry::activity!("approval_activity", approval_activity);
+
+/// Waits for someone to approve `request` out-of-band. The task token goes out with the
+/// request (e.g. in a Slack button), and the activity stays open until it is completed with
+/// that token.
+async fn approval_activity(ctx: ActContext, request: String) -> Result<ActExitValue<String>> {
+    let token = async_completion::task_token(&ctx);
+    tracing::info!(%request, %token, "waiting for approval");
+
+    Ok(ActExitValue::WillCompleteAsync)
+}
+
 #[derive(Serialize, Deserialize)]
 struct TestActInput {
     name: String,
     team: String,
 }
 
+registry::activity!("test_activity_fn", test_activity_fn, max_per_second = 5.0);
+
 async fn test_activity_fn(ctx: ActContext, input: TestActInput) -> Result<String> {
-    println!("{:?} - Activity time before waiting", Instant::now());
-    tokio::time::sleep(Duration::from_secs(5)).await;
-    println!("{:?} - Activity time AFTER waiting", Instant::now());
+    tracing::info!(now = ?Instant::now(), "activity time before waiting");
+    ctx.heartbeat_json(&"waiting")?;
+
+    // heartbeat while waiting so a cancelled workflow stops the activity early
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_secs(5)) => {}
+        _ = ctx.heartbeat_loop(Duration::from_secs(1)) => bail!("test_activity_fn was cancelled"),
+    }
+    tracing::info!(now = ?Instant::now(), "activity time after waiting");
 
     let msg = format!(
         "Hello {}, from team {}",
@@ -66,7 +575,7 @@ async fn test_activity_fn(ctx: ActContext, input: TestActInput) -> Result<String
         input.team.to_uppercase()
     );
 
-    println!("from activity: {}", &msg);
+    tracing::info!(%msg, "from activity");
     Ok(msg)
 }
 
@@ -76,15 +585,12 @@ struct TestWFInput {
     team: String,
 }
 
+// testing new stuff for workflow functions
+registry::workflow!("test_workflow_fn", test_workflow_fn);
+
 /// Current core_sdk won't let you return anything from WF
 // async fn test_workflow_fn(input: TestWFInput) -> Result<String> {
-async fn test_workflow_fn(ctx: WfContext) -> Result<WfExitValue<()>> {
-    // workflow inputs need to be manually deserialized into their actual type(s)
-    let args = ctx.get_args();
-    let input: TestWFInput =
-        serde_json::from_slice(&args.first().expect("No argument passed to workflow").data)
-            .expect("Failed to deserialize wf arg into expected input struct");
-
+async fn test_workflow_fn(ctx: WfContext, input: TestWFInput) -> Result<WfExitValue<()>> {
     // testing log from workflow
     let msg = format!(
         "Hello {}, from team {}",
@@ -92,39 +598,174 @@ async fn test_workflow_fn(ctx: WfContext) -> Result<WfExitValue<()>> {
         input.team.to_uppercase()
     );
 
-    // testing time from workflow
-    println!("{:?} - Workflow time before Activity", Instant::now());
+    // workflow code must stay deterministic, so time/uuids/randomness come from wf_utils
+    let mut rng = wf_utils::WfRng::new(&ctx);
+    tracing::info!(request_id = %rng.uuid(), "workflow request id");
+
+    wf_utils::jittered_sleep(
+        &ctx,
+        &mut rng,
+        Duration::from_secs(1),
+        Duration::from_millis(500),
+    )
+    .await;
+
+    tracing::info!(now = ?wf_utils::workflow_now(&ctx)?, "workflow time before activity");
 
     // wait for activity to finish. activity sleeps for 5 seconds and writes some logs, returning a string
-    let resp = ctx
-        .activity(ActivityOptions {
-            activity_type: "test_activity_fn".to_string(),
-            start_to_close_timeout: Some(Duration::from_secs(50)),
+    let resp = workflow_interceptor::activity(
+        &ctx,
+        ActivityOptions {
+            heartbeat_timeout: Some(Duration::from_secs(3)),
             // activity fn can only take a single argument
-            input: input.as_json_payload().expect("serializes fine"),
-            ..Default::default()
-        })
-        .await;
+            ..wf_activities::activity_options("test_activity_fn", &input)?
+        },
+    )
+    .await;
 
-    println!("{:?} - Workflow time after Activity", Instant::now());
+    tracing::info!(now = ?wf_utils::workflow_now(&ctx)?, "workflow time after activity");
 
-    println!("activity resp debug: {:?}", &resp);
+    tracing::debug!(?resp, "activity resp");
 
-    let activity_output_bytes = match resp.status {
-        Some(finished) => match finished {
-            Status::Completed(success) => success.result.expect("no result returned").data,
-            _ => todo!(),
-        },
-        _ => todo!(),
-    };
+    let activity_output: String = wf_activities::activity_result(resp)?;
 
-    println!(
-        "activity resp data: {}",
-        String::from_utf8(activity_output_bytes).expect("Activity didn't return a string Type")
-    );
+    tracing::info!(%activity_output, "activity resp data");
 
-    println!("from workflow: {}", &msg);
+    // cheap, idempotent calls can run as local activities on this worker
+    let echoed: String = wf_activities::activity_result(
+        workflow_interceptor::local_activity(
+            &ctx,
+            wf_activities::local_activity_options("echo_activity", &msg, Duration::from_secs(5))?,
+        )
+        .await,
+    )?;
+
+    tracing::info!(%echoed, "local activity echoed");
+
+    tracing::info!(%msg, "from workflow");
 
     // Ok(WfExitValue::Normal(()))
     Ok(().into())
 }
+
+#[derive(Serialize, Deserialize)]
+struct PollWFInput {
+    target: String,
+    polls_so_far: u32,
+    max_polls: u32,
+}
+
+/// Polls this many times per run before continuing as new, to keep history small.
+const POLLS_PER_RUN: u32 = 10;
+
+registry::workflow!("poll_workflow_fn", poll_workflow_fn);
+
+/// Long-running polling example: checks on `target` until `max_polls` is reached, starting a
+/// fresh run every [POLLS_PER_RUN] polls.
+async fn poll_workflow_fn(ctx: WfContext, input: PollWFInput) -> Result<WfExitValue<()>> {
+    let mut rng = wf_utils::WfRng::new(&ctx);
+
+    for poll in 0..POLLS_PER_RUN {
+        let polls_so_far = input.polls_so_far + poll;
+        if polls_so_far >= input.max_polls {
+            tracing::info!(poll_target = %input.target, polls_so_far, "finished polling");
+            return Ok(().into());
+        }
+
+        let status: String = wf_activities::activity_result(
+            workflow_interceptor::activity(
+                &ctx,
+                wf_activities::activity_options(
+                    "echo_activity",
+                    &format!("{} poll #{}", input.target, polls_so_far),
+                )?,
+            )
+            .await,
+        )?;
+        tracing::info!(%status, "poll status");
+
+        wf_utils::jittered_sleep(
+            &ctx,
+            &mut rng,
+            Duration::from_secs(30),
+            Duration::from_secs(5),
+        )
+        .await;
+    }
+
+    worker_ext::continue_as_new_with(
+        &ctx,
+        "poll_workflow_fn",
+        &PollWFInput {
+            polls_so_far: input.polls_so_far + POLLS_PER_RUN,
+            ..input
+        },
+    )
+}
+
+#[derive(Serialize, Deserialize)]
+struct SagaWFInput {
+    user: String,
+    /// fail the last step to see the earlier steps get compensated
+    fail_last_step: bool,
+}
+
+registry::workflow!("saga_workflow_fn", saga_workflow_fn);
+
+/// Remediation example: each step records how to undo itself, and everything done so far is
+/// rolled back if a later step fails.
+async fn saga_workflow_fn(ctx: WfContext, input: SagaWFInput) -> Result<WfExitValue<()>> {
+    let ctx = &ctx;
+    let user = input.user.as_str();
+    let mut saga = saga::Saga::new();
+
+    let result = async {
+        saga.run_step(echo_step(ctx, format!("disable {}", user)), move || {
+            echo_step(ctx, format!("re-enable {}", user))
+        })
+        .await?;
+
+        saga.run_step(
+            echo_step(ctx, format!("revoke keys of {}", user)),
+            move || echo_step(ctx, format!("restore keys of {}", user)),
+        )
+        .await?;
+
+        if input.fail_last_step {
+            bail!("notifying {} failed", user);
+        }
+        echo_step(ctx, format!("notify {}", user)).await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!(error = ?e, "saga failed, compensating");
+        saga.compensate().await?;
+        return Err(e);
+    }
+
+    Ok(().into())
+}
+
+/// Schedules `echo_activity`, standing in for a real remediation activity.
+fn echo_step(ctx: &WfContext, msg: String) -> impl Future<Output = Result<String>> {
+    let activity = wf_activities::activity_options("echo_activity", &msg)
+        .map(|opts| workflow_interceptor::activity(ctx, opts));
+
+    async move { wf_activities::activity_result(activity?.await) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_times_as_rfc3339() {
+        assert_eq!(format_time(None), "-");
+        assert_eq!(format_time(Some(UNIX_EPOCH)), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_time(Some(UNIX_EPOCH + Duration::from_secs(1_709_210_096))),
+            "2024-02-29T12:34:56Z"
+        );
+    }
+}
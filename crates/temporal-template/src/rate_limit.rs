@@ -0,0 +1,73 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use temporal_sdk::ActContext;
+
+/// Spaces out calls evenly so no more than `max_per_second` start in any second. Only limits
+/// the calls that share this limiter: [crate::registry::activity!] builds one per worker, and
+/// the server-side limit across workers is
+/// [crate::settings::WorkerSettings::max_task_queue_activities_per_second].
+pub struct RateLimiter {
+    interval: Duration,
+    next_start: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Panics unless `max_per_second` is positive.
+    pub fn per_second(max_per_second: f64) -> Self {
+        assert!(
+            max_per_second > 0.0,
+            "rate limit must be positive, got {} per second",
+            max_per_second
+        );
+
+        Self {
+            interval: Duration::from_secs_f64(1.0 / max_per_second),
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for this call's turn.
+    pub async fn acquire(&self) {
+        let start_at = {
+            let mut next_start = self.next_start.lock();
+            let start_at = (*next_start).max(Instant::now());
+            *next_start = start_at + self.interval;
+            start_at
+        };
+
+        tokio::time::sleep_until(tokio::time::Instant::from_std(start_at)).await;
+    }
+}
+
+/// Wraps `activity_fn` so calls wait on `limiter` before running, e.g. to stay under a
+/// third party API's quota when many activities are retried at once. Time spent waiting
+/// counts towards the activity's start to close timeout.
+pub fn rate_limited<A, R, F, Fut>(
+    limiter: RateLimiter,
+    activity_fn: F,
+) -> impl Fn(ActContext, A) -> BoxFuture<'static, Result<R>> + Send + Sync + 'static
+where
+    A: Send + 'static,
+    R: Send + 'static,
+    F: Fn(ActContext, A) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R>> + Send + 'static,
+{
+    let limiter = Arc::new(limiter);
+    let activity_fn = Arc::new(activity_fn);
+
+    move |ctx: ActContext, input: A| {
+        let limiter = limiter.clone();
+        let activity_fn = activity_fn.clone();
+
+        Box::pin(async move {
+            limiter.acquire().await;
+            activity_fn(ctx, input).await
+        })
+    }
+}
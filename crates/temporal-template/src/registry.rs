@@ -0,0 +1,73 @@
+use temporal_sdk::Worker;
+
+/// An activity or workflow to register on every worker built by the template. Submitted
+/// next to the function it registers with [activity!] or [workflow!], and collected at link
+/// time so there is no central list to keep up to date.
+pub struct Registration {
+    pub name: &'static str,
+    pub register: fn(&mut Worker),
+}
+
+inventory::collect!(Registration);
+
+/// Registers every [Registration] submitted anywhere in the crate.
+pub fn register_all(worker: &mut Worker) {
+    for registration in inventory::iter::<Registration> {
+        tracing::debug!(name = registration.name, "registering");
+        (registration.register)(worker);
+    }
+}
+
+/// Submits an activity function for registration under `$name`, wrapped in the
+/// [crate::activity_middleware] chain. Add `max_per_second = <f64>` to limit how often this
+/// activity type starts on each worker (see [crate::rate_limit::rate_limited]).
+macro_rules! activity {
+    ($name:literal, $activity_fn:path) => {
+        inventory::submit! {
+            $crate::registry::Registration {
+                name: $name,
+                register: |worker| {
+                    worker.register_activity(
+                        $name,
+                        $crate::activity_middleware::with_middleware($name, $activity_fn),
+                    )
+                },
+            }
+        }
+    };
+    ($name:literal, $activity_fn:path, max_per_second = $rate:expr) => {
+        inventory::submit! {
+            $crate::registry::Registration {
+                name: $name,
+                register: |worker| {
+                    let limited = $crate::rate_limit::rate_limited(
+                        $crate::rate_limit::RateLimiter::per_second($rate),
+                        $activity_fn,
+                    );
+                    worker.register_activity(
+                        $name,
+                        $crate::activity_middleware::with_middleware($name, limited),
+                    )
+                },
+            }
+        }
+    };
+}
+
+/// Submits a workflow function taking typed input (see
+/// [crate::worker_ext::WorkerExt::register_wf_typed]) for registration under `$name`.
+macro_rules! workflow {
+    ($name:literal, $wf_fn:path) => {
+        inventory::submit! {
+            $crate::registry::Registration {
+                name: $name,
+                register: |worker| {
+                    $crate::worker_ext::WorkerExt::register_wf_typed(worker, $name, $wf_fn)
+                },
+            }
+        }
+    };
+}
+
+pub(crate) use activity;
+pub(crate) use workflow;
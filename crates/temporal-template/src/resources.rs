@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use std::fs;
+
+/// Sizes the number of concurrent activities from the CPU and memory available to the
+/// process, so one worker binary fits whatever pod size it is deployed into.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SlotSizing {
+    /// Concurrent activities per available CPU.
+    pub activities_per_cpu: usize,
+    /// Memory to reserve for each concurrent activity.
+    pub memory_per_activity_mb: u64,
+}
+
+impl Default for SlotSizing {
+    fn default() -> Self {
+        Self {
+            activities_per_cpu: 10,
+            memory_per_activity_mb: 64,
+        }
+    }
+}
+
+impl SlotSizing {
+    /// The smaller of the CPU and memory based limits, and at least 1. Uses only the CPU
+    /// limit when the memory limit can't be read.
+    pub fn max_outstanding_activities(&self) -> usize {
+        let cpus = std::thread::available_parallelism()
+            .map(|cpus| cpus.get())
+            .unwrap_or(1);
+        let by_cpu = cpus * self.activities_per_cpu;
+
+        let by_memory = available_memory_bytes()
+            .and_then(|bytes| bytes.checked_div(self.memory_per_activity_mb * 1024 * 1024))
+            .map(|slots| slots as usize);
+
+        let slots = by_memory
+            .map_or(by_cpu, |by_memory| by_cpu.min(by_memory))
+            .max(1);
+
+        tracing::info!(cpus, by_cpu, ?by_memory, slots, "sized activity slots");
+        slots
+    }
+}
+
+/// The cgroup memory limit (v2, then v1) when running in a container, otherwise the
+/// machine's total memory.
+pub fn available_memory_bytes() -> Option<u64> {
+    let cgroup_limit = [
+        "/sys/fs/cgroup/memory.max",
+        "/sys/fs/cgroup/memory/memory.limit_in_bytes",
+    ]
+    .iter()
+    .filter_map(|path| fs::read_to_string(path).ok())
+    // v2 reports "max" when unlimited
+    .find_map(|limit| limit.trim().parse::<u64>().ok());
+
+    let total = fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| {
+            meminfo
+                .lines()
+                .find(|line| line.starts_with("MemTotal:"))
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|kb| kb.parse::<u64>().ok())
+                .map(|kb| kb * 1024)
+        });
+
+    // v1 reports a huge number when unlimited, so cap at the machine's memory
+    match (cgroup_limit, total) {
+        (Some(limit), Some(total)) => Some(limit.min(total)),
+        (limit, total) => limit.or(total),
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use std::future::Future;
+
+type Compensation<'a> = Box<dyn FnOnce() -> BoxFuture<'a, Result<()>> + Send + 'a>;
+
+/// Records a compensation for each workflow step that succeeds, so the steps can be undone
+/// in reverse order if a later one fails.
+///
+/// ```ignore
+/// let mut saga = Saga::new();
+/// let result = async {
+///     saga.run_step(disable_user(&ctx), || enable_user(&ctx)).await?;
+///     saga.run_step(rotate_keys(&ctx), || restore_keys(&ctx)).await
+/// }
+/// .await;
+///
+/// if let Err(e) = result {
+///     saga.compensate().await?;
+///     return Err(e);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Saga<'a> {
+    compensations: Vec<Compensation<'a>>,
+}
+
+impl<'a> Saga<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `compensation` to be run if the saga is compensated.
+    pub fn add_compensation<T, F, Fut>(&mut self, compensation: F)
+    where
+        F: FnOnce() -> Fut + Send + 'a,
+        Fut: Future<Output = Result<T>> + Send + 'a,
+    {
+        self.compensations.push(Box::new(move || {
+            Box::pin(async move { compensation().await.map(|_| ()) })
+        }));
+    }
+
+    /// Awaits `step`, recording `compensation` only if the step succeeded.
+    pub async fn run_step<T, C, F, Fut>(
+        &mut self,
+        step: impl Future<Output = Result<T>> + Send,
+        compensation: F,
+    ) -> Result<T>
+    where
+        F: FnOnce() -> Fut + Send + 'a,
+        Fut: Future<Output = Result<C>> + Send + 'a,
+    {
+        let output = step.await?;
+        self.add_compensation(compensation);
+        Ok(output)
+    }
+
+    /// Runs the recorded compensations, most recent first. Every compensation is attempted
+    /// even if an earlier one fails; the failures are reported together.
+    pub async fn compensate(self) -> Result<()> {
+        let mut failures = vec![];
+
+        for compensation in self.compensations.into_iter().rev() {
+            if let Err(e) = compensation().await {
+                failures.push(format!("{:#}", e));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Compensation failed: {}", failures.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::sync::Mutex;
+
+    async fn undo(log: &Mutex<Vec<&'static str>>, step: &'static str) -> Result<()> {
+        log.lock().unwrap().push(step);
+        Ok(())
+    }
+
+    async fn fail_undo(log: &Mutex<Vec<&'static str>>, step: &'static str) -> Result<()> {
+        log.lock().unwrap().push(step);
+        bail!("undo {} failed", step)
+    }
+
+    #[tokio::test]
+    async fn compensates_in_reverse_order() {
+        let log = Mutex::new(vec![]);
+        let mut saga = Saga::new();
+        saga.add_compensation(|| undo(&log, "first"));
+        saga.add_compensation(|| undo(&log, "second"));
+        saga.add_compensation(|| undo(&log, "third"));
+
+        saga.compensate().await.unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["third", "second", "first"]);
+    }
+
+    #[tokio::test]
+    async fn keeps_compensating_after_a_failure() {
+        let log = Mutex::new(vec![]);
+        let mut saga = Saga::new();
+        saga.add_compensation(|| undo(&log, "first"));
+        saga.add_compensation(|| fail_undo(&log, "second"));
+        saga.add_compensation(|| undo(&log, "third"));
+
+        assert!(saga.compensate().await.is_err());
+        assert_eq!(*log.lock().unwrap(), vec!["third", "second", "first"]);
+    }
+
+    #[tokio::test]
+    async fn reports_failures_together() {
+        let log = Mutex::new(vec![]);
+        let mut saga = Saga::new();
+        saga.add_compensation(|| fail_undo(&log, "first"));
+        saga.add_compensation(|| undo(&log, "second"));
+        saga.add_compensation(|| fail_undo(&log, "third"));
+
+        let err = saga.compensate().await.unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Compensation failed: undo third failed; undo first failed"
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Creates and manages Temporal schedules, which start a workflow on a recurring basis. See
+//! `schedule.example.yaml` for the definition format.
+
+use crate::client::ClientIdentityConfig;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+use temporal_client::WorkflowService;
+use temporal_sdk_core::{protos::coresdk::AsJsonPayloadExt, Client, RetryClient};
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::{Payloads, WorkflowType},
+    enums::v1::{ScheduleOverlapPolicy, TaskQueueKind},
+    schedule::v1::{
+        schedule_action, CalendarSpec, IntervalSpec, Schedule, ScheduleAction as ActionProto,
+        SchedulePolicies as PoliciesProto, ScheduleSpec as SpecProto, ScheduleState,
+    },
+    taskqueue::v1::TaskQueue,
+    workflow::v1::NewWorkflowExecutionInfo,
+    workflowservice::v1::{
+        CreateScheduleRequest, DeleteScheduleRequest, DescribeScheduleRequest,
+        UpdateScheduleRequest,
+    },
+};
+use uuid::Uuid;
+
+/// A schedule: when to run, what to start, and what to do when runs overlap.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleDefinition {
+    pub spec: ScheduleSpec,
+    pub action: ScheduleAction,
+    #[serde(default)]
+    pub policies: SchedulePolicies,
+    /// Create the schedule paused.
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// When a schedule runs. The times of every entry are combined.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleSpec {
+    /// Cron expressions, e.g. `0 12 * * MON-FRI`.
+    pub cron: Vec<String>,
+    pub intervals: Vec<ScheduleInterval>,
+    pub calendars: Vec<ScheduleCalendar>,
+    /// IANA time zone for cron and calendar entries, e.g. `America/New_York`. UTC when
+    /// empty.
+    pub time_zone: String,
+}
+
+/// Runs every `every_secs`, shifted by `offset_secs` (e.g. every hour at 15 minutes past).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleInterval {
+    pub every_secs: u64,
+    #[serde(default)]
+    pub offset_secs: u64,
+}
+
+/// Runs at the matching calendar times. Each field takes a value, a range (`1-5`), a step
+/// (`*/15`) or a comma separated list of them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleCalendar {
+    pub second: String,
+    pub minute: String,
+    pub hour: String,
+    pub day_of_month: String,
+    pub month: String,
+    pub day_of_week: String,
+}
+
+impl Default for ScheduleCalendar {
+    /// Midnight, every day.
+    fn default() -> Self {
+        Self {
+            second: "0".to_string(),
+            minute: "0".to_string(),
+            hour: "0".to_string(),
+            day_of_month: "*".to_string(),
+            month: "*".to_string(),
+            day_of_week: "*".to_string(),
+        }
+    }
+}
+
+/// The workflow each scheduled run starts. The server appends the run's time to
+/// `workflow_id`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleAction {
+    pub workflow_type: String,
+    pub workflow_id: String,
+    pub task_queue: String,
+    /// Json argument passed to the workflow.
+    #[serde(default)]
+    pub input: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulePolicies {
+    pub overlap: OverlapPolicy,
+    /// How late a run missed while the server was down may still start. Server default
+    /// when unset.
+    pub catchup_window_secs: Option<u64>,
+    /// Pause the schedule when a run fails.
+    pub pause_on_failure: bool,
+}
+
+/// What to do when a run is due while the previous one is still running.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Don't start the new run.
+    #[default]
+    Skip,
+    /// Start the new run once the previous one finishes, keeping at most one waiting.
+    BufferOne,
+    /// Start every due run in turn as the previous ones finish.
+    BufferAll,
+    /// Cancel the previous run, then start the new one.
+    CancelOther,
+    /// Terminate the previous run, then start the new one.
+    TerminateOther,
+    /// Start the new run alongside the previous one.
+    AllowAll,
+}
+
+impl ScheduleDefinition {
+    /// Parses a YAML (or JSON) schedule definition.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read schedule {}", path.display()))?;
+
+        serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse schedule {}", path.display()))
+    }
+
+    fn to_proto(&self) -> Result<Schedule> {
+        let spec = SpecProto {
+            cron_string: self.spec.cron.clone(),
+            interval: self
+                .spec
+                .intervals
+                .iter()
+                .map(|interval| -> Result<_> {
+                    Ok(IntervalSpec {
+                        interval: Some(Duration::from_secs(interval.every_secs).try_into()?),
+                        phase: Some(Duration::from_secs(interval.offset_secs).try_into()?),
+                    })
+                })
+                .collect::<Result<_>>()?,
+            calendar: self
+                .spec
+                .calendars
+                .iter()
+                .map(|calendar| CalendarSpec {
+                    second: calendar.second.clone(),
+                    minute: calendar.minute.clone(),
+                    hour: calendar.hour.clone(),
+                    day_of_month: calendar.day_of_month.clone(),
+                    month: calendar.month.clone(),
+                    day_of_week: calendar.day_of_week.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            timezone_name: self.spec.time_zone.clone(),
+            ..Default::default()
+        };
+
+        let action = NewWorkflowExecutionInfo {
+            workflow_id: self.action.workflow_id.clone(),
+            workflow_type: Some(WorkflowType {
+                name: self.action.workflow_type.clone(),
+            }),
+            task_queue: Some(TaskQueue {
+                name: self.action.task_queue.clone(),
+                kind: TaskQueueKind::Normal as i32,
+            }),
+            input: Some(Payloads {
+                payloads: vec![self.action.input.as_json_payload()?],
+            }),
+            ..Default::default()
+        };
+
+        let policies = PoliciesProto {
+            overlap_policy: self.policies.overlap.to_proto() as i32,
+            catchup_window: self
+                .policies
+                .catchup_window_secs
+                .map(|secs| Duration::from_secs(secs).try_into())
+                .transpose()?,
+            pause_on_failure: self.policies.pause_on_failure,
+        };
+
+        Ok(Schedule {
+            spec: Some(spec),
+            action: Some(ActionProto {
+                action: Some(schedule_action::Action::StartWorkflow(action)),
+            }),
+            policies: Some(policies),
+            state: Some(ScheduleState {
+                notes: self.notes.clone(),
+                paused: self.paused,
+                ..Default::default()
+            }),
+        })
+    }
+}
+
+impl OverlapPolicy {
+    fn to_proto(self) -> ScheduleOverlapPolicy {
+        match self {
+            Self::Skip => ScheduleOverlapPolicy::Skip,
+            Self::BufferOne => ScheduleOverlapPolicy::BufferOne,
+            Self::BufferAll => ScheduleOverlapPolicy::BufferAll,
+            Self::CancelOther => ScheduleOverlapPolicy::CancelOther,
+            Self::TerminateOther => ScheduleOverlapPolicy::TerminateOther,
+            Self::AllowAll => ScheduleOverlapPolicy::AllowAll,
+        }
+    }
+}
+
+/// A schedule's state, see [describe_schedule].
+#[derive(Debug, Clone)]
+pub struct ScheduleDescription {
+    pub paused: bool,
+    pub notes: String,
+    /// Workflows the schedule has started so far.
+    pub action_count: i64,
+    /// Ids of the workflows it started that are still running.
+    pub running_workflows: Vec<String>,
+    /// When the next few runs are due.
+    pub next_action_times: Vec<SystemTime>,
+}
+
+pub async fn create_schedule(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    schedule_id: &str,
+    definition: &ScheduleDefinition,
+) -> Result<()> {
+    client
+        .clone()
+        .create_schedule(CreateScheduleRequest {
+            namespace: namespace.to_string(),
+            schedule_id: schedule_id.to_string(),
+            schedule: Some(definition.to_proto()?),
+            identity: ClientIdentityConfig::from_env().identity,
+            request_id: Uuid::new_v4().to_string(),
+            ..Default::default()
+        })
+        .await
+        .with_context(|| format!("Failed to create schedule {}", schedule_id))?;
+
+    Ok(())
+}
+
+pub async fn describe_schedule(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    schedule_id: &str,
+) -> Result<ScheduleDescription> {
+    let response = client
+        .clone()
+        .describe_schedule(DescribeScheduleRequest {
+            namespace: namespace.to_string(),
+            schedule_id: schedule_id.to_string(),
+        })
+        .await
+        .with_context(|| format!("Failed to describe schedule {}", schedule_id))?
+        .into_inner();
+
+    let state = response
+        .schedule
+        .and_then(|schedule| schedule.state)
+        .unwrap_or_default();
+    let info = response.info.unwrap_or_default();
+
+    Ok(ScheduleDescription {
+        paused: state.paused,
+        notes: state.notes,
+        action_count: info.action_count,
+        running_workflows: info
+            .running_workflows
+            .into_iter()
+            .map(|execution| execution.workflow_id)
+            .collect(),
+        next_action_times: info
+            .future_action_times
+            .into_iter()
+            .filter_map(|time| time.try_into().ok())
+            .collect(),
+    })
+}
+
+/// Replaces a schedule's definition. Runs already started are left alone.
+pub async fn update_schedule(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    schedule_id: &str,
+    definition: &ScheduleDefinition,
+) -> Result<()> {
+    client
+        .clone()
+        .update_schedule(UpdateScheduleRequest {
+            namespace: namespace.to_string(),
+            schedule_id: schedule_id.to_string(),
+            schedule: Some(definition.to_proto()?),
+            identity: ClientIdentityConfig::from_env().identity,
+            request_id: Uuid::new_v4().to_string(),
+            ..Default::default()
+        })
+        .await
+        .with_context(|| format!("Failed to update schedule {}", schedule_id))?;
+
+    Ok(())
+}
+
+/// Deletes a schedule. Workflows it started keep running.
+pub async fn delete_schedule(
+    client: &RetryClient<Client>,
+    namespace: &str,
+    schedule_id: &str,
+) -> Result<()> {
+    client
+        .clone()
+        .delete_schedule(DeleteScheduleRequest {
+            namespace: namespace.to_string(),
+            schedule_id: schedule_id.to_string(),
+            identity: ClientIdentityConfig::from_env().identity,
+        })
+        .await
+        .with_context(|| format!("Failed to delete schedule {}", schedule_id))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_example_schedule() {
+        let definition: ScheduleDefinition =
+            serde_yaml::from_str(include_str!("../schedule.example.yaml")).unwrap();
+        let schedule = definition.to_proto().unwrap();
+
+        let spec = schedule.spec.unwrap();
+        assert_eq!(spec.cron_string, vec!["0 9 * * MON-FRI".to_string()]);
+        assert_eq!(spec.interval.len(), 1);
+        assert_eq!(spec.calendar[0].day_of_month, "1");
+        assert_eq!(
+            schedule.policies.unwrap().overlap_policy,
+            ScheduleOverlapPolicy::BufferOne as i32
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let err = serde_yaml::from_str::<ScheduleDefinition>(
+            "spec: { crons: ['* * * * *'] }\naction: { workflow_type: a, workflow_id: b, task_queue: c }\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("crons"), "{}", err);
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+static SECRETS: OnceCell<Box<dyn SecretsProvider>> = OnceCell::new();
+
+/// Somewhere secrets (API keys, TLS keys) are read from, so credentials don't have to be
+/// passed as plain env vars.
+pub trait SecretsProvider: Send + Sync {
+    /// The secret called `name`, or `None` if this provider doesn't have it.
+    fn get(&self, name: &str) -> Result<Option<String>>;
+}
+
+/// Secrets set as env vars, named as the secret.
+pub struct EnvSecrets;
+
+impl SecretsProvider for EnvSecrets {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(env::var(name).ok())
+    }
+}
+
+/// Secrets stored as one file per secret in `dir`, named as the secret, as Kubernetes
+/// mounts them.
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl SecretsProvider for FileSecrets {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let path = self.dir.join(name);
+
+        match fs::read_to_string(&path) {
+            Ok(secret) => Ok(Some(secret.trim_end().to_string())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read secret {}", path.display())),
+        }
+    }
+}
+
+/// Asks each provider in turn, returning the first secret found.
+pub struct SecretsChain(pub Vec<Box<dyn SecretsProvider>>);
+
+impl SecretsChain {
+    /// Env vars, then files in `SECRETS_DIR` if it is set.
+    pub fn from_env() -> Self {
+        let mut providers: Vec<Box<dyn SecretsProvider>> = vec![Box::new(EnvSecrets)];
+        if let Ok(dir) = env::var("SECRETS_DIR") {
+            providers.push(Box::new(FileSecrets::new(dir)));
+        }
+        Self(providers)
+    }
+}
+
+impl SecretsProvider for SecretsChain {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        for provider in &self.0 {
+            if let Some(secret) = provider.get(name)? {
+                return Ok(Some(secret));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Sets where [get] reads secrets from. Call once at startup, before connecting; later
+/// calls are ignored.
+pub fn init(provider: Box<dyn SecretsProvider>) {
+    let _ = SECRETS.set(provider);
+}
+
+/// Reads `name` from the provider set with [init], or from [SecretsChain::from_env].
+pub fn get(name: &str) -> Result<Option<String>> {
+    SECRETS
+        .get_or_init(|| Box::new(SecretsChain::from_env()))
+        .get(name)
+}
@@ -0,0 +1,576 @@
+use crate::{
+    app_env::AppEnv,
+    backlog::BacklogSettings,
+    discovery::{DnsSrvDiscovery, ServiceDiscovery, StaticDiscovery},
+    env_vars::{get_env_or_default, get_env_parsed, get_service_url},
+    resources::SlotSizing,
+};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use temporal_sdk_core::{TelemetryOptions, TelemetryOptionsBuilder, Url};
+use temporal_sdk_core_api::{
+    telemetry::{Logger, MetricsExporter, OtelCollectorOptions, TraceExportConfig, TraceExporter},
+    worker::{WorkerConfig, WorkerConfigBuilder},
+};
+use tracing_subscriber::EnvFilter;
+
+/// Everything the worker needs to know about where it runs, loaded from the TOML or YAML
+/// file named by `WORKER_CONFIG` (see `worker.example.toml`), then overridden by env vars.
+/// Missing fields keep their defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct WorkerSettings {
+    /// Temporal frontends to connect to, tried in order.
+    pub temporal_urls: Vec<String>,
+    /// DNS SRV record to discover the frontends from instead of `temporal_urls`.
+    pub temporal_srv_record: Option<String>,
+    pub namespace: String,
+    pub task_queue: String,
+    pub worker_build_id: String,
+    pub max_outstanding_workflow_tasks: usize,
+    pub max_outstanding_activities: usize,
+    /// Replaces `max_outstanding_activities` with a limit sized from available CPU and
+    /// memory when set. The limit is for the whole process and is split evenly between the
+    /// workers it runs (see [crate::worker_set::WorkerSet]).
+    pub activity_slot_sizing: Option<SlotSizing>,
+    pub max_outstanding_local_activities: usize,
+    /// Workflows kept in memory between tasks. 0 disables the cache, so every workflow task
+    /// replays the workflow's full history. Sticky task queues are only used when this is
+    /// non-zero.
+    pub max_cached_workflows: usize,
+    /// How long a workflow task may wait on this worker's sticky queue before the server
+    /// hands it to any worker instead (which then replays from history).
+    pub sticky_queue_schedule_to_start_timeout_secs: u64,
+    /// Share of workflow task pollers polling the normal queue rather than the sticky one.
+    pub nonsticky_to_sticky_poll_ratio: f32,
+    /// Concurrent long polls for workflow tasks.
+    pub max_concurrent_wft_polls: usize,
+    /// Concurrent long polls for activity tasks.
+    pub max_concurrent_at_polls: usize,
+    /// Activities this worker starts per second, across all activity types.
+    pub max_worker_activities_per_second: Option<f64>,
+    /// Activities started per second by all workers on the task queue, enforced by the
+    /// server.
+    pub max_task_queue_activities_per_second: Option<f64>,
+    /// Address to serve `/healthz` and `/readyz` on for Kubernetes probes, e.g.
+    /// `0.0.0.0:8080`. No health server is started when unset.
+    pub health_bind_address: Option<SocketAddr>,
+    pub backlog: BacklogSettings,
+    pub telemetry: TelemetrySettings,
+}
+
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    /// `tracing` env-filter for everything the worker logs, core SDK included. Falls back to
+    /// `RUST_LOG`, then `info`. Read once at startup.
+    pub log_level: Option<String>,
+    /// Extra env-filter directives for core SDK logs, appended to `log_level`, e.g.
+    /// `temporal_sdk_core=debug`.
+    pub log_filter: Option<String>,
+    /// How logs are printed. Read once at startup.
+    pub log_format: LogFormat,
+    /// Address to serve core SDK metrics on for Prometheus to scrape, e.g. `0.0.0.0:9464`.
+    /// Metrics are off when unset.
+    pub prometheus_bind_address: Option<SocketAddr>,
+    /// OpenTelemetry collector to export core SDK traces to over OTLP/gRPC, e.g.
+    /// `http://localhost:4317`. Tracing is off when unset.
+    pub otlp_endpoint: Option<String>,
+    /// Extra headers sent to the collector, e.g. for auth. Their values are left out of
+    /// debug output.
+    pub otlp_headers: HashMap<String, String>,
+    /// `tracing` env-filter for exported spans. Defaults to `temporal_sdk_core=info`.
+    pub trace_filter: Option<String>,
+}
+
+impl fmt::Debug for TelemetrySettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // header values may be credentials, and settings are logged at startup
+        let otlp_headers: HashMap<_, _> = self
+            .otlp_headers
+            .keys()
+            .map(|name| (name, "<redacted>"))
+            .collect();
+
+        f.debug_struct("TelemetrySettings")
+            .field("log_level", &self.log_level)
+            .field("log_filter", &self.log_filter)
+            .field("log_format", &self.log_format)
+            .field("prometheus_bind_address", &self.prometheus_bind_address)
+            .field("otlp_endpoint", &self.otlp_endpoint)
+            .field("otlp_headers", &otlp_headers)
+            .field("trace_filter", &self.trace_filter)
+            .finish()
+    }
+}
+
+/// Output format for logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines from core's console logger, for local development.
+    #[default]
+    Text,
+    /// One json object per line, for log aggregation in production. Printed by
+    /// [crate::logging::JsonLogPrinter].
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => bail!("Unknown log format {}, expected text or json", s),
+        }
+    }
+}
+
+impl TelemetrySettings {
+    /// Core SDK telemetry options for these settings.
+    ///
+    /// Core installs the process's global `tracing` subscriber, so the worker's own logs go
+    /// through core's logger along with core's: printed by its console logger for
+    /// [LogFormat::Text], or buffered for [crate::logging::JsonLogPrinter] to print for
+    /// [LogFormat::Json].
+    pub fn telemetry_options(&self) -> Result<TelemetryOptions> {
+        let mut telemetry_options = TelemetryOptionsBuilder::default().build()?;
+        let filter = self.log_filter_directives()?;
+        telemetry_options.logging = Some(match self.log_format {
+            LogFormat::Text => Logger::Console { filter },
+            LogFormat::Json => Logger::Forward { filter },
+        });
+        telemetry_options.metrics = self
+            .prometheus_bind_address
+            .map(MetricsExporter::Prometheus);
+        telemetry_options.tracing = self
+            .otlp_endpoint
+            .as_deref()
+            .map(|endpoint| -> Result<_> {
+                Ok(TraceExportConfig {
+                    filter: self
+                        .trace_filter
+                        .clone()
+                        .unwrap_or_else(|| "temporal_sdk_core=info".to_string()),
+                    exporter: TraceExporter::Otel(OtelCollectorOptions {
+                        url: Url::from_str(endpoint)
+                            .with_context(|| format!("Invalid OTLP endpoint: {}", endpoint))?,
+                        headers: self.otlp_headers.clone(),
+                        metric_periodicity: None,
+                    }),
+                })
+            })
+            .transpose()?;
+
+        Ok(telemetry_options)
+    }
+
+    /// [TelemetrySettings::log_level] followed by [TelemetrySettings::log_filter].
+    fn log_filter_directives(&self) -> Result<String> {
+        let mut filter = self
+            .log_level
+            .clone()
+            .or_else(|| env::var("RUST_LOG").ok())
+            .unwrap_or_else(|| "info".to_string());
+        if let Some(core_filter) = &self.log_filter {
+            filter = format!("{},{}", filter, core_filter);
+        }
+
+        EnvFilter::try_new(&filter).with_context(|| format!("Invalid log filter {:?}", filter))?;
+        Ok(filter)
+    }
+}
+
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            temporal_urls: vec!["http://localhost:7233".to_string()],
+            temporal_srv_record: None,
+            namespace: "security-engineering".to_string(),
+            task_queue: "task_queue".to_string(),
+            worker_build_id: "some_unique_thing".to_string(),
+            max_outstanding_workflow_tasks: 100,
+            max_outstanding_activities: 100,
+            activity_slot_sizing: None,
+            max_outstanding_local_activities: 100,
+            max_cached_workflows: 0,
+            sticky_queue_schedule_to_start_timeout_secs: 10,
+            nonsticky_to_sticky_poll_ratio: 0.2,
+            max_concurrent_wft_polls: 5,
+            max_concurrent_at_polls: 5,
+            max_worker_activities_per_second: None,
+            max_task_queue_activities_per_second: None,
+            health_bind_address: None,
+            backlog: BacklogSettings::default(),
+            telemetry: TelemetrySettings::default(),
+        }
+    }
+}
+
+impl WorkerSettings {
+    /// Loads the `.env` files for the `app_env` profile, then the
+    /// [WorkerSettings::config_path] file, and applies env overrides.
+    ///
+    /// Resolve `app_env` once, before any `.env` file is loaded, and pass the same profile
+    /// everywhere; an `APP_ENV` set in a `.env` file doesn't change it.
+    pub fn load(app_env: AppEnv) -> Result<Self> {
+        app_env.load_dotenv()?;
+        Self::load_config(app_env)
+    }
+
+    /// Loads the [WorkerSettings::config_path] file and applies env overrides, without
+    /// touching the `.env` files. Used to reload while the worker runs, since loading `.env`
+    /// files sets env vars, which isn't safe once other threads are running.
+    pub fn load_config(app_env: AppEnv) -> Result<Self> {
+        let mut settings = match Self::config_path(app_env)? {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+
+        settings.apply_env_overrides()?;
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// The file named by `WORKER_CONFIG`, or `app_env`'s `worker.<profile>.toml` if it
+    /// exists.
+    pub fn config_path(app_env: AppEnv) -> Result<Option<PathBuf>> {
+        if let Ok(path) = env::var("WORKER_CONFIG") {
+            return Ok(Some(PathBuf::from(path)));
+        }
+
+        let profile_file = PathBuf::from(app_env.worker_config_file());
+        Ok(profile_file.exists().then_some(profile_file))
+    }
+
+    /// Parses a `.toml`, `.yaml` or `.yml` settings file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read worker config {}", path.display()))?;
+
+        let settings = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(anyhow::Error::from),
+            Some("yaml" | "yml") => serde_yaml::from_str(&contents).map_err(anyhow::Error::from),
+            _ => bail!(
+                "Unsupported worker config format {}, expected .toml, .yaml or .yml",
+                path.display()
+            ),
+        };
+
+        settings.with_context(|| format!("Failed to parse worker config {}", path.display()))
+    }
+
+    /// Rejects settings the worker would otherwise only trip over once it starts polling.
+    fn validate(&self) -> Result<()> {
+        for (name, rate) in [
+            (
+                "max_worker_activities_per_second",
+                self.max_worker_activities_per_second,
+            ),
+            (
+                "max_task_queue_activities_per_second",
+                self.max_task_queue_activities_per_second,
+            ),
+        ] {
+            if let Some(rate) = rate.filter(|rate| !(rate.is_finite() && *rate > 0.0)) {
+                bail!("{} must be a positive number, got {}", name, rate);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Env vars win over the config file:
+    /// - `TEMPORAL_URLS` (comma separated) or `TEMPORAL_URL`, falling back to
+    ///   `TEMPORAL_SERVICE_URL` or `TEMPORAL_HOST`/`TEMPORAL_PORT` (see [get_service_url])
+    /// - `TEMPORAL_SRV_RECORD`
+    /// - `TEMPORAL_NAMESPACE`, `TEMPORAL_TASK_QUEUE`, `WORKER_BUILD_ID`
+    /// - `WORKER_MAX_OUTSTANDING_WORKFLOW_TASKS`, `WORKER_MAX_OUTSTANDING_ACTIVITIES`,
+    ///   `WORKER_MAX_OUTSTANDING_LOCAL_ACTIVITIES`, `WORKER_MAX_CACHED_WORKFLOWS`
+    /// - `WORKER_ACTIVITIES_PER_CPU`, `WORKER_MEMORY_PER_ACTIVITY_MB` (either turns on
+    ///   activity slot sizing)
+    /// - `WORKER_MAX_CONCURRENT_WFT_POLLS`, `WORKER_MAX_CONCURRENT_AT_POLLS`
+    /// - `WORKER_MAX_ACTIVITIES_PER_SECOND`, `WORKER_MAX_TASK_QUEUE_ACTIVITIES_PER_SECOND`
+    /// - `WORKER_STICKY_QUEUE_SCHEDULE_TO_START_TIMEOUT_SECS`,
+    ///   `WORKER_NONSTICKY_TO_STICKY_POLL_RATIO`
+    /// - `WORKER_HEALTH_BIND_ADDRESS`, `WORKER_BACKLOG_CHECK_INTERVAL_SECS`
+    /// - `LOG_LEVEL`, `TEMPORAL_LOG_FILTER`, `LOG_FORMAT`, `PROMETHEUS_BIND_ADDRESS`
+    /// - `OTEL_EXPORTER_OTLP_ENDPOINT`, `TEMPORAL_TRACE_FILTER`
+    fn apply_env_overrides(&mut self) -> Result<()> {
+        if let Ok(urls) = env::var("TEMPORAL_URLS").or_else(|_| env::var("TEMPORAL_URL")) {
+            self.temporal_urls = urls.split(',').map(|url| url.trim().to_string()).collect();
+        } else if let Some(url) = get_service_url("temporal", 7233)? {
+            self.temporal_urls = vec![url.to_string()];
+        }
+        if let Ok(record) = env::var("TEMPORAL_SRV_RECORD") {
+            self.temporal_srv_record = Some(record);
+        }
+        if let Ok(namespace) = env::var("TEMPORAL_NAMESPACE") {
+            self.namespace = namespace;
+        }
+        if let Ok(task_queue) = env::var("TEMPORAL_TASK_QUEUE") {
+            self.task_queue = task_queue;
+        }
+        if let Ok(worker_build_id) = env::var("WORKER_BUILD_ID") {
+            self.worker_build_id = worker_build_id;
+        }
+        self.max_outstanding_workflow_tasks = get_env_or_default(
+            "WORKER_MAX_OUTSTANDING_WORKFLOW_TASKS",
+            self.max_outstanding_workflow_tasks,
+        )?;
+        self.max_outstanding_activities = get_env_or_default(
+            "WORKER_MAX_OUTSTANDING_ACTIVITIES",
+            self.max_outstanding_activities,
+        )?;
+        self.max_outstanding_local_activities = get_env_or_default(
+            "WORKER_MAX_OUTSTANDING_LOCAL_ACTIVITIES",
+            self.max_outstanding_local_activities,
+        )?;
+        if let Some(per_cpu) = get_env_parsed("WORKER_ACTIVITIES_PER_CPU")? {
+            self.activity_slot_sizing
+                .get_or_insert_with(SlotSizing::default)
+                .activities_per_cpu = per_cpu;
+        }
+        if let Some(mb) = get_env_parsed("WORKER_MEMORY_PER_ACTIVITY_MB")? {
+            self.activity_slot_sizing
+                .get_or_insert_with(SlotSizing::default)
+                .memory_per_activity_mb = mb;
+        }
+        self.max_cached_workflows =
+            get_env_or_default("WORKER_MAX_CACHED_WORKFLOWS", self.max_cached_workflows)?;
+        self.sticky_queue_schedule_to_start_timeout_secs = get_env_or_default(
+            "WORKER_STICKY_QUEUE_SCHEDULE_TO_START_TIMEOUT_SECS",
+            self.sticky_queue_schedule_to_start_timeout_secs,
+        )?;
+        self.nonsticky_to_sticky_poll_ratio = get_env_or_default(
+            "WORKER_NONSTICKY_TO_STICKY_POLL_RATIO",
+            self.nonsticky_to_sticky_poll_ratio,
+        )?;
+        self.max_concurrent_wft_polls = get_env_or_default(
+            "WORKER_MAX_CONCURRENT_WFT_POLLS",
+            self.max_concurrent_wft_polls,
+        )?;
+        self.max_concurrent_at_polls = get_env_or_default(
+            "WORKER_MAX_CONCURRENT_AT_POLLS",
+            self.max_concurrent_at_polls,
+        )?;
+        if let Some(rate) = get_env_parsed("WORKER_MAX_ACTIVITIES_PER_SECOND")? {
+            self.max_worker_activities_per_second = Some(rate);
+        }
+        if let Some(rate) = get_env_parsed("WORKER_MAX_TASK_QUEUE_ACTIVITIES_PER_SECOND")? {
+            self.max_task_queue_activities_per_second = Some(rate);
+        }
+        if let Some(address) = get_env_parsed("WORKER_HEALTH_BIND_ADDRESS")? {
+            self.health_bind_address = Some(address);
+        }
+        if let Some(secs) = get_env_parsed("WORKER_BACKLOG_CHECK_INTERVAL_SECS")? {
+            self.backlog.check_interval_secs = Some(secs);
+        }
+        if let Ok(log_level) = env::var("LOG_LEVEL") {
+            self.telemetry.log_level = Some(log_level);
+        }
+        if let Ok(log_filter) = env::var("TEMPORAL_LOG_FILTER") {
+            self.telemetry.log_filter = Some(log_filter);
+        }
+        if let Some(log_format) = get_env_parsed("LOG_FORMAT")? {
+            self.telemetry.log_format = log_format;
+        }
+        if let Some(address) = get_env_parsed("PROMETHEUS_BIND_ADDRESS")? {
+            self.telemetry.prometheus_bind_address = Some(address);
+        }
+        if let Ok(endpoint) = env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            self.telemetry.otlp_endpoint = Some(endpoint);
+        }
+        if let Ok(trace_filter) = env::var("TEMPORAL_TRACE_FILTER") {
+            self.telemetry.trace_filter = Some(trace_filter);
+        }
+
+        Ok(())
+    }
+
+    /// Discovers the frontends through DNS when `temporal_srv_record` is set, otherwise uses
+    /// `temporal_urls`. SRV targets use `https` if the first of `temporal_urls` does.
+    pub fn service_discovery(&self) -> Result<Box<dyn ServiceDiscovery>> {
+        let urls = self.temporal_urls()?;
+
+        Ok(match &self.temporal_srv_record {
+            Some(record) => Box::new(DnsSrvDiscovery {
+                record: record.clone(),
+                scheme: urls.first().map_or("http", |url| url.scheme()).to_string(),
+            }),
+            None => Box::new(StaticDiscovery { urls }),
+        })
+    }
+
+    /// [WorkerSettings::temporal_urls] parsed, skipping blank entries.
+    pub fn temporal_urls(&self) -> Result<Vec<Url>> {
+        self.temporal_urls
+            .iter()
+            .map(|url| url.trim())
+            .filter(|url| !url.is_empty())
+            .map(|url| Url::from_str(url).with_context(|| format!("Invalid Temporal url: {}", url)))
+            .collect()
+    }
+
+    /// The activity limit for each of `worker_count` workers in this process: the
+    /// [WorkerSettings::activity_slot_sizing] budget split between them, or
+    /// `max_outstanding_activities` each when slots aren't sized.
+    pub fn max_outstanding_activities_per_worker(&self, worker_count: usize) -> usize {
+        match &self.activity_slot_sizing {
+            Some(sizing) => (sizing.max_outstanding_activities() / worker_count.max(1)).max(1),
+            None => self.max_outstanding_activities,
+        }
+    }
+
+    /// Worker config for polling `task_queue` in `namespace` with these settings, running at
+    /// most `max_outstanding_activities` at once (see
+    /// [WorkerSettings::max_outstanding_activities_per_worker]).
+    pub fn worker_config(
+        &self,
+        namespace: &str,
+        task_queue: &str,
+        max_outstanding_activities: usize,
+    ) -> Result<WorkerConfig> {
+        let mut builder = WorkerConfigBuilder::default();
+        builder
+            .namespace(namespace)
+            .task_queue(task_queue)
+            .worker_build_id(&self.worker_build_id)
+            .max_outstanding_workflow_tasks(self.max_outstanding_workflow_tasks)
+            .max_outstanding_activities(max_outstanding_activities)
+            .max_outstanding_local_activities(self.max_outstanding_local_activities)
+            .max_cached_workflows(self.max_cached_workflows)
+            .sticky_queue_schedule_to_start_timeout(Duration::from_secs(
+                self.sticky_queue_schedule_to_start_timeout_secs,
+            ))
+            .nonsticky_to_sticky_poll_ratio(self.nonsticky_to_sticky_poll_ratio)
+            .max_concurrent_wft_polls(self.max_concurrent_wft_polls)
+            .max_concurrent_at_polls(self.max_concurrent_at_polls);
+
+        if let Some(rate) = self.max_worker_activities_per_second {
+            builder.max_worker_activities_per_second(rate);
+        }
+        if let Some(rate) = self.max_task_queue_activities_per_second {
+            builder.max_task_queue_activities_per_second(rate);
+        }
+
+        Ok(builder.build()?)
+    }
+
+    /// Names of the settings that differ between `self` and `other` but are only read at
+    /// startup, i.e. everything except `backlog` (see [crate::config_reload]).
+    pub fn changes_needing_restart(&self, other: &Self) -> Vec<&'static str> {
+        macro_rules! changed {
+            ($($field:ident),*) => {{
+                // destructured so a new field can't be left out of the comparison
+                let Self { $($field: _,)* backlog: _ } = self;
+                [$((stringify!($field), self.$field != other.$field)),*]
+                    .into_iter()
+                    .filter_map(|(name, changed)| changed.then_some(name))
+                    .collect()
+            }};
+        }
+
+        changed!(
+            temporal_urls,
+            temporal_srv_record,
+            namespace,
+            task_queue,
+            worker_build_id,
+            max_outstanding_workflow_tasks,
+            max_outstanding_activities,
+            activity_slot_sizing,
+            max_outstanding_local_activities,
+            max_cached_workflows,
+            sticky_queue_schedule_to_start_timeout_secs,
+            nonsticky_to_sticky_poll_ratio,
+            max_concurrent_wft_polls,
+            max_concurrent_at_polls,
+            max_worker_activities_per_second,
+            max_task_queue_activities_per_second,
+            health_bind_address,
+            telemetry
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backlog_changes_dont_need_a_restart() {
+        let initial = WorkerSettings::default();
+
+        let mut reloaded = initial.clone();
+        reloaded.backlog.scale_up_threshold += 1;
+        assert!(reloaded.changes_needing_restart(&initial).is_empty());
+
+        reloaded.max_worker_activities_per_second = Some(5.0);
+        reloaded.telemetry.log_level = Some("debug".to_string());
+        assert_eq!(
+            reloaded.changes_needing_restart(&initial),
+            vec!["max_worker_activities_per_second", "telemetry"]
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_activity_rates() {
+        let settings = WorkerSettings {
+            max_worker_activities_per_second: Some(2.5),
+            max_task_queue_activities_per_second: Some(100.0),
+            ..Default::default()
+        };
+        settings.validate().unwrap();
+
+        for rate in [0.0, -1.0, f64::NAN] {
+            let settings = WorkerSettings {
+                max_worker_activities_per_second: Some(rate),
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err(), "{}", rate);
+
+            let settings = WorkerSettings {
+                max_task_queue_activities_per_second: Some(rate),
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err(), "{}", rate);
+        }
+    }
+
+    #[test]
+    fn parses_log_format() {
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("yaml".parse::<LogFormat>().is_err());
+
+        let telemetry: TelemetrySettings = toml::from_str("log_format = \"json\"").unwrap();
+        assert_eq!(telemetry.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn debug_hides_otlp_header_values() {
+        let telemetry = TelemetrySettings {
+            otlp_headers: HashMap::from([(
+                "authorization".to_string(),
+                "Bearer s3cret".to_string(),
+            )]),
+            ..Default::default()
+        };
+
+        let debug = format!("{:?}", telemetry);
+        assert!(debug.contains("authorization"), "{}", debug);
+        assert!(!debug.contains("s3cret"), "{}", debug);
+    }
+}
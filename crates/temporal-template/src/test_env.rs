@@ -0,0 +1,148 @@
+//! Runs workflows in tests against Temporal's time-skipping test server, with a worker that
+//! has every registered activity and workflow. While a workflow runs the server skips ahead
+//! to its next timer whenever nothing else is in flight, so timer-heavy workflows finish in
+//! milliseconds.
+//!
+//! The test server is downloaded on first use, so tests using this need network access.
+
+use crate::{client, registry, settings::WorkerSettings};
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{str::FromStr, sync::Arc};
+use temporal_client::TestService;
+use temporal_sdk::{sdk_client_options, Worker};
+use temporal_sdk_core::{
+    ephemeral_server::{
+        EphemeralExe, EphemeralExeVersion, EphemeralServer, TestServerConfigBuilder,
+    },
+    init_worker, Client, RetryClient, Url,
+};
+use temporal_sdk_core_protos::temporal::api::testservice::v1::{
+    LockTimeSkippingRequest, UnlockTimeSkippingRequest,
+};
+use uuid::Uuid;
+
+const NAMESPACE: &str = "default";
+
+pub struct TestEnv {
+    server: EphemeralServer,
+    client: RetryClient<Client>,
+}
+
+impl TestEnv {
+    /// Starts the test server and connects to it.
+    pub async fn start() -> Result<Self> {
+        let server = TestServerConfigBuilder::default()
+            .exe(EphemeralExe::CachedDownload {
+                version: EphemeralExeVersion::Default {
+                    sdk_name: "sdk-rust".to_string(),
+                    sdk_version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                dest_dir: None,
+            })
+            .build()?
+            .start_server()
+            .await
+            .context("Failed to start the Temporal test server")?;
+
+        let url = Url::from_str(&format!("http://{}", server.target))?;
+        let client = sdk_client_options(url)
+            .build()?
+            .connect(NAMESPACE, None, None)
+            .await?;
+
+        Ok(Self { server, client })
+    }
+
+    /// Runs `workflow_type` with `input` to completion, skipping time while it runs, and
+    /// returns its json result. Errors (with a [client::WorkflowError]) if the workflow
+    /// doesn't complete successfully.
+    ///
+    /// Each call runs on a fresh task queue with its own worker, which stops once the
+    /// workflow finishes, so calls don't see each other's workflows.
+    pub async fn execute_workflow_and_skip_time<A: Serialize, T: DeserializeOwned>(
+        &self,
+        workflow_type: &str,
+        input: &A,
+    ) -> Result<T> {
+        let task_queue = format!("test-{}", Uuid::new_v4());
+        let workflow_id = format!("{}-{}", workflow_type, Uuid::new_v4());
+
+        let settings = WorkerSettings::default();
+        let worker_config =
+            settings.worker_config(NAMESPACE, &task_queue, settings.max_outstanding_activities)?;
+        let mut worker = Worker::new_from_core(
+            Arc::new(init_worker(worker_config, self.client.clone())),
+            &task_queue,
+        );
+        registry::register_all(&mut worker);
+        // stop the worker once this workflow finishes
+        worker.incr_expected_run_count(1);
+
+        let run_id = client::start_workflow(
+            &self.client,
+            NAMESPACE,
+            &task_queue,
+            &workflow_id,
+            workflow_type,
+            input,
+        )
+        .await?;
+
+        // the test server only skips time while unlocked
+        self.client
+            .clone()
+            .unlock_time_skipping(UnlockTimeSkippingRequest::default())
+            .await?;
+        let run = worker.run().await;
+        self.client
+            .clone()
+            .lock_time_skipping(LockTimeSkippingRequest::default())
+            .await?;
+        run?;
+
+        client::workflow_result(&self.client, NAMESPACE, &workflow_id, &run_id).await
+    }
+
+    pub async fn shutdown(mut self) -> Result<()> {
+        self.server.shutdown().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn skips_long_timers() {
+        let env = TestEnv::start().await.unwrap();
+
+        let started = std::time::Instant::now();
+        env.execute_workflow_and_skip_time::<_, ()>(
+            "dsl_workflow",
+            &json!({ "steps": [{ "sleep": { "seconds": 24 * 60 * 60 } }] }),
+        )
+        .await
+        .unwrap();
+        assert!(started.elapsed().as_secs() < 30);
+
+        env.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn runs_several_workflows() {
+        let env = TestEnv::start().await.unwrap();
+
+        for seconds in [60, 60 * 60] {
+            env.execute_workflow_and_skip_time::<_, ()>(
+                "dsl_workflow",
+                &json!({ "steps": [{ "sleep": { "seconds": seconds } }] }),
+            )
+            .await
+            .unwrap();
+        }
+
+        env.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use crate::env_vars::get_env_parsed;
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::OnceCell;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{env, time::Duration};
+use temporal_sdk::{ActivityOptions, LocalActivityOptions};
+use temporal_sdk_core::protos::coresdk::AsJsonPayloadExt;
+use temporal_sdk_core_protos::{
+    coresdk::activity_result::{activity_resolution::Status, ActivityResolution},
+    temporal::api::common::v1::RetryPolicy,
+};
+
+static ACTIVITY_DEFAULTS: OnceCell<ActivityDefaults> = OnceCell::new();
+
+/// Timeouts and retry policy applied to every activity scheduled through
+/// [activity_options] and [local_activity_options], so they are set once per deployment
+/// instead of at every call site.
+#[derive(Debug, Clone)]
+pub struct ActivityDefaults {
+    pub start_to_close_timeout: Duration,
+    pub schedule_to_close_timeout: Option<Duration>,
+    /// `None` leaves retries to the server's default policy.
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+impl Default for ActivityDefaults {
+    fn default() -> Self {
+        Self {
+            start_to_close_timeout: Duration::from_secs(60),
+            schedule_to_close_timeout: None,
+            retry_policy: None,
+        }
+    }
+}
+
+impl ActivityDefaults {
+    /// Reads overrides of the built-in defaults from:
+    /// - `ACTIVITY_START_TO_CLOSE_TIMEOUT_SECS` / `ACTIVITY_SCHEDULE_TO_CLOSE_TIMEOUT_SECS`
+    /// - `ACTIVITY_RETRY_INITIAL_INTERVAL_MS` / `ACTIVITY_RETRY_MAX_INTERVAL_MS`
+    /// - `ACTIVITY_RETRY_BACKOFF_COEFFICIENT`
+    /// - `ACTIVITY_RETRY_MAX_ATTEMPTS`
+    /// - `ACTIVITY_RETRY_NON_RETRYABLE_ERRORS`: comma separated error types
+    ///
+    /// Setting any `ACTIVITY_RETRY_*` variable replaces the server's default retry policy.
+    pub fn from_env() -> Result<Self> {
+        let mut defaults = Self::default();
+
+        if let Some(secs) = get_env_parsed("ACTIVITY_START_TO_CLOSE_TIMEOUT_SECS")? {
+            defaults.start_to_close_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = get_env_parsed("ACTIVITY_SCHEDULE_TO_CLOSE_TIMEOUT_SECS")? {
+            defaults.schedule_to_close_timeout = Some(Duration::from_secs(secs));
+        }
+
+        let initial_interval: Option<u64> = get_env_parsed("ACTIVITY_RETRY_INITIAL_INTERVAL_MS")?;
+        let maximum_interval: Option<u64> = get_env_parsed("ACTIVITY_RETRY_MAX_INTERVAL_MS")?;
+        let backoff_coefficient = get_env_parsed("ACTIVITY_RETRY_BACKOFF_COEFFICIENT")?;
+        let maximum_attempts = get_env_parsed("ACTIVITY_RETRY_MAX_ATTEMPTS")?;
+        let non_retryable_error_types = env::var("ACTIVITY_RETRY_NON_RETRYABLE_ERRORS").ok();
+
+        if initial_interval.is_some()
+            || maximum_interval.is_some()
+            || backoff_coefficient.is_some()
+            || maximum_attempts.is_some()
+            || non_retryable_error_types.is_some()
+        {
+            defaults.retry_policy = Some(RetryPolicy {
+                initial_interval: initial_interval
+                    .map(|ms| Duration::from_millis(ms).try_into())
+                    .transpose()?,
+                maximum_interval: maximum_interval
+                    .map(|ms| Duration::from_millis(ms).try_into())
+                    .transpose()?,
+                backoff_coefficient: backoff_coefficient.unwrap_or(2.0),
+                // 0 means unlimited attempts
+                maximum_attempts: maximum_attempts.unwrap_or(0),
+                non_retryable_error_types: non_retryable_error_types
+                    .map(|types| {
+                        types
+                            .split(',')
+                            .map(|t| t.trim().to_string())
+                            .filter(|t| !t.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(defaults)
+    }
+
+    /// Sets the defaults used by the option helpers. Call once at worker startup; later
+    /// calls are ignored.
+    pub fn init(defaults: ActivityDefaults) {
+        let _ = ACTIVITY_DEFAULTS.set(defaults);
+    }
+
+    /// The defaults set with [ActivityDefaults::init], or the built-in ones.
+    pub fn global() -> &'static ActivityDefaults {
+        ACTIVITY_DEFAULTS.get_or_init(ActivityDefaults::default)
+    }
+}
+
+/// Options for scheduling `activity_type` with [ActivityDefaults] applied. Override fields
+/// at the call site with struct update syntax:
+///
+/// ```ignore
+/// ctx.activity(ActivityOptions {
+///     heartbeat_timeout: Some(Duration::from_secs(3)),
+///     ..activity_options("my_activity", &input)?
+/// })
+/// ```
+pub fn activity_options<T: Serialize>(activity_type: &str, input: &T) -> Result<ActivityOptions> {
+    let defaults = ActivityDefaults::global();
+
+    Ok(ActivityOptions {
+        activity_type: activity_type.to_string(),
+        input: input.as_json_payload()?,
+        start_to_close_timeout: Some(defaults.start_to_close_timeout),
+        schedule_to_close_timeout: defaults.schedule_to_close_timeout,
+        retry_policy: defaults.retry_policy.clone(),
+        ..Default::default()
+    })
+}
+
+/// Options for running `activity_type` as a local activity, i.e. inside the worker that is
+/// running the workflow instead of through an activity task. Meant for short, idempotent
+/// calls that aren't worth a round trip to the server. [ActivityDefaults] apply, except for
+/// the start to close timeout.
+pub fn local_activity_options<T: Serialize>(
+    activity_type: &str,
+    input: &T,
+    start_to_close_timeout: Duration,
+) -> Result<LocalActivityOptions> {
+    let defaults = ActivityDefaults::global();
+
+    Ok(LocalActivityOptions {
+        activity_type: activity_type.to_string(),
+        input: input.as_json_payload()?,
+        start_to_close_timeout: Some(start_to_close_timeout),
+        schedule_to_close_timeout: defaults.schedule_to_close_timeout,
+        retry_policy: defaults.retry_policy.clone().unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+/// Deserializes the json result of a completed activity (local or not), turning failed and
+/// cancelled activities into errors.
+pub fn activity_result<T: DeserializeOwned>(resolution: ActivityResolution) -> Result<T> {
+    match resolution.status {
+        Some(Status::Completed(success)) => {
+            let payload = success
+                .result
+                .ok_or_else(|| anyhow!("Activity completed without a result"))?;
+            serde_json::from_slice(&payload.data).with_context(|| {
+                format!(
+                    "Failed to deserialize activity result into {}",
+                    std::any::type_name::<T>()
+                )
+            })
+        }
+        Some(Status::Failed(failed)) => bail!(
+            "Activity failed: {}",
+            failed.failure.map(|f| f.message).unwrap_or_default()
+        ),
+        Some(Status::Cancelled(cancelled)) => bail!(
+            "Activity cancelled: {}",
+            cancelled.failure.map(|f| f.message).unwrap_or_default()
+        ),
+        Some(Status::Backoff(_)) => bail!("Local activity is backing off"),
+        None => bail!("Activity resolved without a status"),
+    }
+}
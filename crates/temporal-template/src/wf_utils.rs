@@ -0,0 +1,71 @@
+//! Deterministic replacements for things workflow code is tempted to reach for directly.
+//! `Instant::now()`, `uuid::Uuid::new_v4()` and `rand` return something different on every
+//! replay of a workflow, which breaks determinism; these helpers derive everything from
+//! [WfContext] instead.
+
+use anyhow::{anyhow, Result};
+use std::{
+    future::Future,
+    time::{Duration, SystemTime},
+};
+use temporal_sdk::WfContext;
+use uuid::Uuid;
+
+/// Current workflow time, as recorded in history. Same on every replay.
+pub fn workflow_now(ctx: &WfContext) -> Result<SystemTime> {
+    ctx.workflow_time()
+        .ok_or_else(|| anyhow!("Workflow time is not available before the first activation"))
+}
+
+/// Random number generator seeded from the workflow's random seed, so the same sequence is
+/// produced on every replay.
+pub struct WfRng {
+    state: u64,
+}
+
+impl WfRng {
+    pub fn new(ctx: &WfContext) -> Self {
+        Self {
+            state: ctx.random_seed(),
+        }
+    }
+
+    /// splitmix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A v4-formatted uuid built from this generator's output.
+    pub fn uuid(&mut self) -> Uuid {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.next_u64().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next_u64().to_le_bytes());
+        uuid::Builder::from_random_bytes(bytes).into_uuid()
+    }
+
+    /// A duration between zero and `max`.
+    pub fn jitter(&mut self, max: Duration) -> Duration {
+        match max.as_millis() as u64 {
+            0 => Duration::ZERO,
+            max_millis => Duration::from_millis(self.next_u64() % (max_millis + 1)),
+        }
+    }
+}
+
+/// Sleeps on a workflow timer for `base` plus up to `max_jitter`, e.g. to spread out polling
+/// workflows that were all started at once.
+pub fn jittered_sleep(
+    ctx: &WfContext,
+    rng: &mut WfRng,
+    base: Duration,
+    max_jitter: Duration,
+) -> impl Future<Output = ()> {
+    let timer = ctx.timer(base + rng.jitter(max_jitter));
+    async move {
+        timer.await;
+    }
+}
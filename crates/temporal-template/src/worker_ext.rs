@@ -0,0 +1,89 @@
+use crate::workflow_interceptor::interceptors;
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use temporal_sdk::{WfContext, WfExitValue, Worker};
+use temporal_sdk_core::protos::coresdk::AsJsonPayloadExt;
+use temporal_sdk_core_protos::coresdk::workflow_commands::ContinueAsNewWorkflowExecution;
+
+/// Registration helpers layered on top of [Worker].
+pub trait WorkerExt {
+    /// Registers a workflow whose first argument is deserialized from json into `A` before
+    /// `wf_function` is called. Missing or malformed input fails the workflow instead of
+    /// panicking the workflow task. Runs the [crate::workflow_interceptor] hooks around the
+    /// workflow.
+    fn register_wf_typed<A, F, Fut>(&mut self, workflow_type: impl Into<String>, wf_function: F)
+    where
+        A: DeserializeOwned + Send + 'static,
+        F: Fn(WfContext, A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<WfExitValue<()>>> + Send + 'static;
+}
+
+impl WorkerExt for Worker {
+    fn register_wf_typed<A, F, Fut>(&mut self, workflow_type: impl Into<String>, wf_function: F)
+    where
+        A: DeserializeOwned + Send + 'static,
+        F: Fn(WfContext, A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<WfExitValue<()>>> + Send + 'static,
+    {
+        let workflow_type = workflow_type.into();
+        let wf_name = workflow_type.clone();
+
+        self.register_wf(workflow_type, move |ctx: WfContext| {
+            for i in interceptors() {
+                i.on_start(&wf_name);
+            }
+
+            let wf = deserialize_wf_input::<A>(&ctx, &wf_name).map(|input| wf_function(ctx, input));
+            let wf_name = wf_name.clone();
+
+            async move {
+                let result = match wf {
+                    Ok(wf) => wf.await,
+                    Err(e) => Err(e),
+                };
+                for i in interceptors() {
+                    i.on_complete(&wf_name, &result);
+                }
+                result
+            }
+        });
+    }
+}
+
+/// Deserializes the first workflow argument into `A`.
+pub fn deserialize_wf_input<A: DeserializeOwned>(
+    ctx: &WfContext,
+    workflow_type: &str,
+) -> Result<A> {
+    let arg = ctx
+        .get_args()
+        .first()
+        .ok_or_else(|| anyhow!("No argument passed to workflow {}", workflow_type))?;
+
+    serde_json::from_slice(&arg.data).with_context(|| {
+        format!(
+            "Failed to deserialize {} arg into {}",
+            workflow_type,
+            std::any::type_name::<A>()
+        )
+    })
+}
+
+/// Exit value that continues `workflow_type` as a new run on the same task queue, with `args`
+/// serialized as its json input. Pairs with [WorkerExt::register_wf_typed] on the receiving
+/// end, and keeps long-running workflows from growing an unbounded history.
+pub fn continue_as_new_with<A: Serialize>(
+    ctx: &WfContext,
+    workflow_type: &str,
+    args: &A,
+) -> Result<WfExitValue<()>> {
+    Ok(WfExitValue::ContinueAsNew(Box::new(
+        ContinueAsNewWorkflowExecution {
+            workflow_type: workflow_type.to_string(),
+            task_queue: ctx.task_queue().to_string(),
+            arguments: vec![args.as_json_payload()?],
+            ..Default::default()
+        },
+    )))
+}
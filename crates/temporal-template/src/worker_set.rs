@@ -0,0 +1,178 @@
+use crate::{
+    client::{check_api_key_namespace, describe_task_queue, ClientIdentityConfig},
+    health::HealthState,
+    settings::WorkerSettings,
+};
+use anyhow::Result;
+use std::{sync::Arc, time::Duration};
+use temporal_sdk::Worker;
+use temporal_sdk_core::{init_worker, Client, RetryClient};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+
+/// One worker to run as part of a [WorkerSet].
+pub struct WorkerDefinition {
+    pub namespace: String,
+    pub task_queue: String,
+    /// Registers the activities and workflows this worker serves.
+    pub register: fn(&mut Worker),
+}
+
+/// Several workers (e.g. one per task queue) run by one process, polling through clones of
+/// the same client and shut down together.
+pub struct WorkerSet {
+    client: RetryClient<Client>,
+    workers: Vec<Worker>,
+    /// The namespace and task queue of each worker.
+    queues: Vec<(String, String)>,
+    health: Option<Arc<HealthState>>,
+}
+
+impl WorkerSet {
+    /// Builds a worker per definition. Settings other than namespace and task queue
+    /// (concurrency, caching, build id) are shared by every worker, except that a sized
+    /// activity slot budget is split between them.
+    ///
+    /// With an API key, every definition must use the client's namespace (see
+    /// [check_api_key_namespace]).
+    pub fn new(
+        client: RetryClient<Client>,
+        settings: &WorkerSettings,
+        definitions: Vec<WorkerDefinition>,
+    ) -> Result<Self> {
+        let max_outstanding_activities =
+            settings.max_outstanding_activities_per_worker(definitions.len());
+
+        let queues = definitions
+            .iter()
+            .map(|definition| (definition.namespace.clone(), definition.task_queue.clone()))
+            .collect();
+
+        let workers = definitions
+            .into_iter()
+            .map(|definition| {
+                check_api_key_namespace(&client, &definition.namespace)?;
+
+                let worker_config = settings.worker_config(
+                    &definition.namespace,
+                    &definition.task_queue,
+                    max_outstanding_activities,
+                )?;
+                let core_worker = init_worker(worker_config, client.clone());
+
+                let mut worker =
+                    Worker::new_from_core(Arc::new(core_worker), &definition.task_queue);
+                (definition.register)(&mut worker);
+
+                tracing::info!(
+                    namespace = %definition.namespace,
+                    task_queue = %definition.task_queue,
+                    "built worker"
+                );
+                Ok(worker)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            client,
+            workers,
+            queues,
+            health: None,
+        })
+    }
+
+    /// Reports polling and shutdown to `health` while running.
+    pub fn with_health(mut self, health: Arc<HealthState>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Runs every worker until they all stop. Ctrl-c or SIGTERM gracefully shuts all of them
+    /// down; any worker failing stops the rest.
+    pub async fn run(mut self) -> Result<()> {
+        let shutdown_handles: Vec<_> = self.workers.iter().map(|w| w.shutdown_handle()).collect();
+
+        let run_all = futures::future::try_join_all(self.workers.iter_mut().map(|w| w.run()));
+        tokio::pin!(run_all);
+
+        let report_polling = self.health.clone().map(|health| {
+            tokio::spawn(report_polling(
+                self.client.clone(),
+                self.queues.clone(),
+                health,
+            ))
+        });
+
+        let result = tokio::select! {
+            res = &mut run_all => res.map(|_| ()),
+            received = shutdown_signal() => {
+                tracing::info!("{} received, shutting down workers", received?);
+                if let Some(health) = &self.health {
+                    health.set_shutting_down();
+                }
+                shutdown_handles.iter().for_each(|shutdown| shutdown());
+                run_all.await.map(|_| ())
+            }
+        };
+
+        if let Some(report_polling) = report_polling {
+            report_polling.abort();
+        }
+        result
+    }
+}
+
+/// Marks `health` as polling once Temporal lists this process (by client identity) as a
+/// poller on every one of `queues`, so readiness waits for the workers to actually poll.
+async fn report_polling(
+    client: RetryClient<Client>,
+    queues: Vec<(String, String)>,
+    health: Arc<HealthState>,
+) {
+    let identity = ClientIdentityConfig::from_env().identity;
+    let mut waiting = queues;
+
+    while !waiting.is_empty() {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let mut still_waiting = vec![];
+        for (namespace, task_queue) in waiting {
+            match describe_task_queue(&client, &namespace, &task_queue).await {
+                Ok(description)
+                    if description
+                        .workflow_pollers
+                        .iter()
+                        .chain(&description.activity_pollers)
+                        .any(|poller| poller.identity == identity) => {}
+                Ok(_) => still_waiting.push((namespace, task_queue)),
+                Err(err) => {
+                    tracing::warn!(%namespace, %task_queue, "failed to check pollers: {:#}", err);
+                    still_waiting.push((namespace, task_queue));
+                }
+            }
+        }
+        waiting = still_waiting;
+    }
+
+    tracing::info!("all workers are polling");
+    health.set_polling();
+}
+
+/// Waits for ctrl-c, or for SIGTERM (what Kubernetes sends before killing a pod) on unix,
+/// returning which arrived.
+async fn shutdown_signal() -> Result<&'static str> {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => res.map(|_| "ctrl-c").map_err(Into::into),
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("ctrl-c")
+    }
+}
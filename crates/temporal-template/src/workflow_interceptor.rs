@@ -0,0 +1,99 @@
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use once_cell::sync::OnceCell;
+use temporal_sdk::{
+    ActivityOptions, CancellableFuture, LocalActivityOptions, SignalData, WfContext, WfExitValue,
+};
+use temporal_sdk_core_protos::coresdk::activity_result::ActivityResolution;
+
+static WORKFLOW_INTERCEPTORS: OnceCell<Vec<Box<dyn WorkflowInterceptor>>> = OnceCell::new();
+
+/// Hooks run around workflows registered through
+/// [crate::worker_ext::WorkerExt::register_wf_typed], for tracing and audit concerns that
+/// apply to every workflow. All hooks default to doing nothing.
+///
+/// Hooks run inside workflow code, so they run again every time a workflow is replayed and
+/// must not affect what the workflow does.
+pub trait WorkflowInterceptor: Send + Sync {
+    /// Runs before the workflow function is called.
+    fn on_start(&self, _workflow_type: &str) {}
+
+    /// Runs when a signal arrives on a channel made with [signal_channel].
+    fn on_signal(&self, _signal_name: &str, _signal: &SignalData) {}
+
+    /// Runs when an activity is scheduled with [activity] or [local_activity].
+    fn on_activity_schedule(&self, _activity_type: &str) {}
+
+    /// Runs with the workflow function's result.
+    fn on_complete(&self, _workflow_type: &str, _result: &Result<WfExitValue<()>>) {}
+}
+
+/// Sets the interceptors run around every workflow, in order. Call once at worker startup,
+/// before the workers are built; later calls are ignored.
+pub fn init(interceptors: Vec<Box<dyn WorkflowInterceptor>>) {
+    let _ = WORKFLOW_INTERCEPTORS.set(interceptors);
+}
+
+pub(crate) fn interceptors() -> &'static [Box<dyn WorkflowInterceptor>] {
+    WORKFLOW_INTERCEPTORS.get_or_init(Vec::new)
+}
+
+/// [WfContext::activity], telling interceptors about the scheduled activity.
+pub fn activity(
+    ctx: &WfContext,
+    opts: ActivityOptions,
+) -> impl CancellableFuture<ActivityResolution> {
+    for i in interceptors() {
+        i.on_activity_schedule(&opts.activity_type);
+    }
+    ctx.activity(opts)
+}
+
+/// [WfContext::local_activity], telling interceptors about the scheduled activity.
+pub fn local_activity(
+    ctx: &WfContext,
+    opts: LocalActivityOptions,
+) -> impl CancellableFuture<ActivityResolution> + '_ {
+    for i in interceptors() {
+        i.on_activity_schedule(&opts.activity_type);
+    }
+    ctx.local_activity(opts)
+}
+
+/// [WfContext::make_signal_channel], telling interceptors about each signal received.
+pub fn signal_channel(ctx: &WfContext, signal_name: &str) -> impl Stream<Item = SignalData> {
+    let name = signal_name.to_string();
+
+    ctx.make_signal_channel(signal_name).map(move |signal| {
+        for i in interceptors() {
+            i.on_signal(&name, &signal);
+        }
+        signal
+    })
+}
+
+/// Logs when workflows start, finish and schedule activities.
+pub struct LogWorkflows;
+
+impl WorkflowInterceptor for LogWorkflows {
+    fn on_start(&self, workflow_type: &str) {
+        tracing::info!(workflow_type, "workflow started");
+    }
+
+    fn on_signal(&self, signal_name: &str, _signal: &SignalData) {
+        tracing::info!(signal_name, "signal received");
+    }
+
+    fn on_activity_schedule(&self, activity_type: &str) {
+        tracing::info!(activity_type, "scheduling activity");
+    }
+
+    fn on_complete(&self, workflow_type: &str, result: &Result<WfExitValue<()>>) {
+        match result {
+            Ok(_) => tracing::info!(workflow_type, "workflow completed"),
+            Err(e) => {
+                tracing::warn!(workflow_type, error = %format!("{:#}", e), "workflow failed")
+            }
+        }
+    }
+}
@@ -1,16 +1,140 @@
 mod flags {
-    use std::path::PathBuf;
-
     xflags::xflags! {
+        cmd xtask {
+            /// Runs the worker against a local Temporal dev server, starting one (and
+            /// installing the Temporal CLI) if nothing is listening on the port.
+            cmd dev {
+                /// Namespace to create and run the worker in.
+                optional --namespace namespace: String
+                /// Dev server frontend port.
+                optional --port port: u16
+            }
+        }
+    }
+}
+
+use std::{
+    env,
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-    cmd my-command {
-        required path: PathBuf
-        optional -v, --verbose
+const DEFAULT_NAMESPACE: &str = "security-engineering";
+const DEFAULT_PORT: u16 = 7233;
+
+fn main() -> Result<()> {
+    match flags::Xtask::from_env_or_exit().subcommand {
+        flags::XtaskCmd::Dev(dev) => run_dev(
+            dev.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE),
+            dev.port.unwrap_or(DEFAULT_PORT),
+        ),
     }
+}
+
+fn run_dev(namespace: &str, port: u16) -> Result<()> {
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let mut dev_server = if is_listening(address) {
+        println!("using temporal server already running on {}", address);
+        create_namespace(namespace, address)?;
+        None
+    } else {
+        Some(start_dev_server(namespace, port, address)?)
+    };
+
+    let status = Command::new(env!("CARGO"))
+        .args(["run", "--package", "temporal-template"])
+        .env("TEMPORAL_URL", format!("http://{}", address))
+        .env("TEMPORAL_NAMESPACE", namespace)
+        .status();
+
+    if let Some(dev_server) = dev_server.as_mut() {
+        println!("stopping temporal dev server");
+        dev_server.kill()?;
+        dev_server.wait()?;
+    }
+
+    let status = status?;
+    if !status.success() {
+        return Err(format!("worker exited with {}", status).into());
     }
+
+    Ok(())
+}
+
+fn is_listening(address: SocketAddr) -> bool {
+    TcpStream::connect_timeout(&address, Duration::from_millis(500)).is_ok()
 }
 
-fn main() {
-    let flags = flags::MyCommand::from_env();
-    println!("{:#?}", flags);
+/// Starts `temporal server start-dev` with `namespace` already created, and waits for it to
+/// accept connections.
+fn start_dev_server(namespace: &str, port: u16, address: SocketAddr) -> Result<Child> {
+    let temporal = temporal_cli()?;
+    println!("starting temporal dev server on {}", address);
+
+    let mut child = Command::new(temporal)
+        .args(["server", "start-dev", "--namespace", namespace])
+        .args(["--port", &port.to_string()])
+        .spawn()?;
+
+    let started = Instant::now();
+    while !is_listening(address) {
+        if let Some(status) = child.try_wait()? {
+            return Err(format!("temporal dev server exited with {}", status).into());
+        }
+        if started.elapsed() > Duration::from_secs(30) {
+            child.kill()?;
+            return Err("temporal dev server did not start within 30s".into());
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+
+    Ok(child)
+}
+
+/// Creates `namespace` on a server this task didn't start. Fails quietly, since the
+/// namespace usually exists already.
+fn create_namespace(namespace: &str, address: SocketAddr) -> Result<()> {
+    let output = Command::new(temporal_cli()?)
+        .args(["operator", "namespace", "create", namespace])
+        .args(["--address", &address.to_string()])
+        .output()?;
+
+    if !output.status.success() {
+        println!(
+            "did not create namespace {}: {}",
+            namespace,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// The Temporal CLI on `PATH`, or in `~/.temporalio/bin`, installing it there if missing.
+fn temporal_cli() -> Result<PathBuf> {
+    if Command::new("temporal").arg("--version").output().is_ok() {
+        return Ok(PathBuf::from("temporal"));
+    }
+
+    let home = env::var("HOME").map_err(|_| "HOME is not set, can't install the Temporal CLI")?;
+    let installed = PathBuf::from(home).join(".temporalio/bin/temporal");
+    if installed.exists() {
+        return Ok(installed);
+    }
+
+    println!("installing the Temporal CLI to {}", installed.display());
+    let status = Command::new("sh")
+        .args(["-c", "curl -sSf https://temporal.download/cli.sh | sh"])
+        .status()?;
+    if !status.success() || !installed.exists() {
+        return Err("failed to install the Temporal CLI, see https://docs.temporal.io/cli".into());
+    }
+
+    Ok(installed)
 }